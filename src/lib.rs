@@ -10,231 +10,944 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::{
     Data::Struct,
-    DataStruct, DeriveInput, Fields,
+    DataStruct, DeriveInput,
     Fields::Named,
-    FieldsNamed, Ident, ItemFn, LitStr, Token,
+    FieldsNamed, Ident, ItemFn, LitStr, Meta, Token,
     parse::{Parse, ParseStream},
     parse_macro_input,
+    punctuated::Punctuated,
 };
 
 mod util;
 use util::*;
 
+/// Options accepted by `#[run_app(...)]`.
+#[derive(Default,)]
+struct RunAppArgs {
+    /// Wrap the app in `Cors::permissive()`.
+    cors: bool,
+    /// Wrap the app in `Compress::default()`.
+    compress: bool,
+    /// Name of the generated fn, `run` unless given.
+    name: Option<Ident,>,
+    /// Leave out the redis-backed session middleware and its `hmac_secret`/`redis_uri` params.
+    no_session: bool,
+    /// Take an `extra: Extra` param registered as one more `app_data`.
+    extra: bool,
+}
+
+impl Parse for RunAppArgs {
+    fn parse(input: ParseStream<'_,>,) -> syn::Result<Self,> {
+        let mut args = Self::default();
+        for meta in Punctuated::<Meta, Token![,],>::parse_terminated(input,)? {
+            match &meta {
+                Meta::Path(p,) if p.is_ident("cors",) => args.cors = true,
+                Meta::Path(p,) if p.is_ident("compress",) => args.compress = true,
+                Meta::Path(p,) if p.is_ident("no_session",) => args.no_session = true,
+                Meta::Path(p,) if p.is_ident("extra",) => args.extra = true,
+                Meta::NameValue(nv,) if nv.path.is_ident("name",) => {
+                    let name = match &nv.value {
+                        syn::Expr::Path(p,) => p.path.get_ident().cloned(),
+                        _ => None,
+                    };
+                    match name {
+                        Some(name,) => args.name = Some(name,),
+                        None => {
+                            return Err(syn::Error::new_spanned(
+                                &nv.value,
+                                "expected a fn name, e.g. name = build_admin_server",
+                            ),);
+                        }
+                    }
+                }
+                _ => return Err(syn::Error::new_spanned(meta, "unknown #[run_app] option",),),
+            }
+        }
+        Ok(args,)
+    }
+}
+
+/// Generates `async fn run(...)` serving an actix `App` with the tracing, session and shared
+/// data middleware wired in. Each statement of the annotated function's body is appended to the
+/// `App` builder chain in order, so `service(health_check); configure(routes);` becomes
+/// `.service(health_check).configure(routes)`.
+///
+/// Options:
+/// - `cors`: add `.wrap(Cors::permissive())`.
+/// - `compress`: add `.wrap(Compress::default())`.
+/// - `name = ident`: name the generated fn, e.g. to build an admin and a public server side by
+///   side in one module.
+/// - `no_session`: for stateless services; no redis store, no session middleware and no
+///   `HmacSecret` app data, so `run` takes neither `hmac_secret` nor `redis_uri`.
+/// - `extra`: `run` takes a last `extra: Extra` param (`Extra: Clone + Send + 'static`),
+///   registered as `app_data` after the custom context, e.g. an email client or feature flags.
+///   Pass a tuple or a struct to share several values.
 #[proc_macro_attribute]
-pub fn run_app(_: TokenStream, input: TokenStream,) -> TokenStream {
-    let input_fn = parse_macro_input!(input as ItemFn);
+pub fn run_app(args: TokenStream, input: TokenStream,) -> TokenStream {
+    expand_run_app(args.into(), input.into(),).into()
+}
 
-    // Avoid indexing panic if the function body is empty.
-    let fn_block = match input_fn.block.stmts.first() {
-        Some(stmt,) => stmt,
-        None => {
-            return syn::Error::new_spanned(
-                &input_fn.sig.ident,
-                "run_app requires at least one statement in the function body",
-            )
-            .to_compile_error()
-            .into();
+fn expand_run_app(
+    args: proc_macro2::TokenStream,
+    input: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let args = match syn::parse2::<RunAppArgs,>(args,) {
+        Ok(args,) => args,
+        Err(e,) => return e.to_compile_error(),
+    };
+    let input_fn = match syn::parse2::<ItemFn,>(input,) {
+        Ok(input_fn,) => input_fn,
+        Err(e,) => return e.to_compile_error(),
+    };
+
+    if input_fn.block.stmts.is_empty() {
+        return syn::Error::new_spanned(
+            &input_fn.sig.ident,
+            "run_app requires at least one statement in the function body",
+        )
+        .to_compile_error();
+    }
+
+    // every statement continues the App builder chain, e.g. `service(a);` then `configure(b)`
+    let mut calls = vec![];
+    for stmt in &input_fn.block.stmts {
+        match stmt {
+            syn::Stmt::Expr(expr, _,) => calls.push(expr,),
+            _ => {
+                return syn::Error::new_spanned(
+                    stmt,
+                    "run_app statements must be App builder calls, e.g. `service(health_check);`",
+                )
+                .to_compile_error();
+            }
         }
+    }
+
+    let cors = if args.cors {
+        quote! { .wrap(Cors::permissive()) }
+    } else {
+        quote! {}
+    };
+    let compress = if args.compress {
+        quote! { .wrap(Compress::default()) }
+    } else {
+        quote! {}
+    };
+
+    let name = args.name.unwrap_or_else(|| Ident::new("run", proc_macro2::Span::call_site(),),);
+
+    // without sessions there is no redis store, and no cookie signing secret to pass around
+    let (session_params, session_store, session_wrap, session_data,) = if args.no_session {
+        (quote! {}, quote! {}, quote! {}, quote! {},)
+    } else {
+        (
+            quote! {
+                hmac_secret: SecretString,
+                redis_uri: SecretString,
+            },
+            quote! { let redis_store = app::redis_session(redis_uri).await?; },
+            quote! {
+                .wrap(app::session_middleware(
+                    hmac_secret.clone(),
+                    redis_store.clone(),
+                ))
+            },
+            quote! { .app_data(web::Data::new(HmacSecret(hmac_secret.clone()))) },
+        )
+    };
+
+    let (extra_generic, extra_param, extra_data,) = if args.extra {
+        (
+            quote! { , Extra: Clone + Send + 'static },
+            quote! { extra: Extra, },
+            quote! { .app_data(web::Data::new(extra.clone())) },
+        )
+    } else {
+        (quote! {}, quote! {}, quote! {},)
     };
 
     quote! {
-    async fn run<Context: Clone + Send + 'static>(
+    async fn #name<Context: Clone + Send + 'static #extra_generic>(
         listener: TcpListener,
         db_pool: PgPool,
         base_url: String,
-        hmac_secret: SecretString,
-        redis_uri: SecretString,
+        #session_params
         custom_context: Context,
+        #extra_param
     ) -> Result<Server, anyhow::Error> {
 
-         let redis_store = app::redis_session(redis_uri).await?;
+         #session_store
          let server = HttpServer::new(move || {
              ActixWebApp::new()
                  .wrap(TracingLogger::default())
-                 .wrap(app::session_middleware(
-                     hmac_secret.clone(),
-                     redis_store.clone(),
-                 ))
+                 #session_wrap
+                 #cors
+                 #compress
                  .app_data(web::Data::new(ApplicationBaseUrl(base_url.clone())))
-                 .app_data(web::Data::new(HmacSecret(hmac_secret.clone())))
+                 #session_data
                  .app_data(web::Data::new(db_pool.clone()))
                  .app_data(web::Data::new(custom_context.clone()))
-             .#fn_block
+                 #extra_data
+                 #(.#calls)*
          })
          .listen(listener)?
          .run();
          Ok(server)
          }
          }
-    .into()
 }
 
 struct Args {
     ctx: Ident,
     schema: LitStr,
     _comma: Token![,],
+    /// Generate the async CRUD helpers (`find_by_id`).
+    crud: bool,
+    /// Generate `InsertRow::execute`, inserting through a pool and mapping the row back.
+    executable: bool,
+    /// Type of the `id` primary key, `i32` unless given as `id = Type`.
+    id_ty: Option<syn::Type,>,
 }
 
 impl Parse for Args {
     fn parse(input: ParseStream<'_,>,) -> syn::Result<Self,> {
-        Ok(Self { ctx: input.parse()?, _comma: input.parse()?, schema: input.parse()?, },)
+        let mut args = Self {
+            ctx: input.parse()?,
+            _comma: input.parse()?,
+            schema: input.parse()?,
+            crud: false,
+            executable: false,
+            id_ty: None,
+        };
+        if args.schema.value().trim().is_empty() {
+            return Err(syn::Error::new_spanned(
+                &args.schema,
+                "the schema table name can't be empty",
+            ),);
+        }
+        let value = args.schema.value();
+        if value.matches('.',).count() > 1 || value.split('.',).any(|p| p.trim().is_empty(),) {
+            return Err(syn::Error::new_spanned(
+                &args.schema,
+                "expected a table name or `schema.table`, e.g. \"public.users\"",
+            ),);
+        }
+        while input.parse::<Option<Token![,],>>()?.is_some() && !input.is_empty() {
+            let flag: Ident = input.parse()?;
+            match flag.to_string().as_str() {
+                "crud" => args.crud = true,
+                "executable" => args.executable = true,
+                "id" => {
+                    input.parse::<Token![=]>()?;
+                    args.id_ty = Some(input.parse()?,);
+                }
+                _ => return Err(syn::Error::new_spanned(flag, "unknown #[schema] option",),),
+            }
+        }
+        Ok(args,)
+    }
+}
+
+/// `#[<tool>(rename = "...")]` (or `rename(...)`) on a field, among the tool's other options, e.g.
+/// `#[serde(rename = "x", default)]`.
+fn is_rename(attr: &syn::Attribute, tool: &str,) -> bool {
+    attr.path().is_ident(tool,)
+        && attr
+            .parse_args_with(Punctuated::<Meta, Token![,],>::parse_terminated,)
+            .is_ok_and(|metas| metas.iter().any(|m| m.path().is_ident("rename",),),)
+}
+
+/// The named fields `schema` rebuilds; anything else errors on the token that makes it unfit,
+/// e.g. the `enum` keyword or a tuple struct's parenthesised fields.
+fn schema_fields(ast: &DeriveInput,) -> syn::Result<&Punctuated<syn::Field, Token![,],>,> {
+    let error = |tokens: &dyn quote::ToTokens, kind: &str| {
+        Err(syn::Error::new_spanned(
+            tokens,
+            format!("schema expected a struct with named fields, found {}", kind),
+        ),)
+    };
+    match &ast.data {
+        Struct(DataStruct { fields: Named(FieldsNamed { named, .. },), .. },) => Ok(named,),
+        Struct(DataStruct { fields: syn::Fields::Unnamed(fields,), .. },) => {
+            error(fields, "tuple struct",)
+        }
+        Struct(DataStruct { struct_token, fields: syn::Fields::Unit, .. },) => {
+            error(struct_token, "unit struct",)
+        }
+        syn::Data::Enum(e,) => error(&e.enum_token, "enum",),
+        syn::Data::Union(u,) => error(&u.union_token, "union",),
     }
 }
 
 #[proc_macro_attribute]
 pub fn schema(args: TokenStream, input: TokenStream,) -> TokenStream {
-    let Args { ctx, schema, .. } = parse_macro_input!(args as Args);
-    let ast = parse_macro_input!(input as DeriveInput);
+    expand_schema(args.into(), input.into(),).into()
+}
+
+fn expand_schema(
+    args: proc_macro2::TokenStream,
+    input: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let Args { ctx, schema, crud, executable, id_ty, .. } = match syn::parse2::<Args,>(args,) {
+        Ok(args,) => args,
+        Err(e,) => return e.to_compile_error(),
+    };
+    let id_ty = id_ty.unwrap_or_else(|| syn::parse_quote!(i32),);
+    let schema_parts = match schema.value().split_once('.',) {
+        Some((namespace, table,),) => quote! { (Some(#namespace), #table) },
+        None => {
+            let table = schema.value();
+            quote! { (None, #table) }
+        }
+    };
+    let ast = match syn::parse2::<DeriveInput,>(input,) {
+        Ok(ast,) => ast,
+        Err(e,) => return e.to_compile_error(),
+    };
 
     let repo_ident = &ast.ident;
-    let repo_attrs = &ast.attrs;
+    // MaeRepo's own helper attributes have to come after the derive that introduces them
+    let (repo_helper_attrs, repo_attrs,): (Vec<_,>, Vec<_,>,) = ast.attrs.iter().partition(|a| {
+        ["row_derive", "arbitrary", "validate",].iter().any(|helper| a.path().is_ident(helper,),)
+    },);
 
     // confirm the macro is being called on a Struct Type and extract the fields.
-    let fields = match ast.data {
-        Struct(DataStruct { fields: Named(FieldsNamed { ref named, .. },), .. },) => named,
-        _ => {
-            return syn::Error::new_spanned(
-                repo_ident,
-                "schema only works for structs with named fields",
+    let fields = match schema_fields(&ast,) {
+        Ok(fields,) => fields,
+        Err(e,) => return e.to_compile_error(),
+    };
+
+    // declaring an injected field is a mistake unless it's explicitly marked as replacing it
+    const INJECTED: [&str; 10] = [
+        "id",
+        "sys_client",
+        "status",
+        "comment",
+        "tags",
+        "sys_detail",
+        "created_by",
+        "updated_by",
+        "created_at",
+        "updated_at",
+    ];
+    let collisions = fields
+        .iter()
+        .filter(|f| f.ident.as_ref().is_some_and(|i| INJECTED.iter().any(|n| i == n,),),)
+        .filter(|f| !f.attrs.iter().any(|a| a.path().is_ident("override_default",),),)
+        .map(|f| {
+            let name = f.ident.as_ref().map(|i| i.to_string(),).unwrap_or_default();
+            syn::Error::new_spanned(
+                f,
+                format!(
+                    "field `{}` is managed by #[schema] and cannot be declared manually; mark it #[override_default] to replace the injected one",
+                    name
+                ),
             )
-            .to_compile_error()
-            .into();
-        }
+        },)
+        .reduce(|mut errors, e| {
+            errors.combine(e,);
+            errors
+        },);
+    if let Some(errors,) = collisions {
+        return errors.to_compile_error();
+    }
+
+    // a default field the user replaced with #[override_default] is left out, so theirs wins
+    let injected = |name: &str, field: proc_macro2::TokenStream| match fields
+        .iter()
+        .any(|f| f.ident.as_ref().is_some_and(|i| i == name,),)
+    {
+        true => quote! {},
+        false => quote! { #field, },
     };
+    let id = injected("id", quote! { #[locked] pub id: #id_ty },);
+    let sys_client = injected("sys_client", quote! { #[insert_only] pub sys_client: i32 },);
+    let status = injected("status", quote! { pub status: mae::repo::default::DomainStatus },);
+    let comment = injected("comment", quote! { pub comment: Option<String> },);
+    let tags = injected("tags", quote! { #[sqlx(json)] pub tags: serde_json::Value },);
+    let sys_detail =
+        injected("sys_detail", quote! { #[sqlx(json)] pub sys_detail: serde_json::Value },);
+    let created_by = injected("created_by", quote! { #[locked] pub created_by: i32 },);
+    let updated_by = injected("updated_by", quote! { #[locked] pub updated_by: i32 },);
+    let created_at =
+        injected("created_at", quote! { #[locked] pub created_at: chrono::DateTime<chrono::Utc> },);
+    let updated_at =
+        injected("updated_at", quote! { #[locked] pub updated_at: chrono::DateTime<chrono::Utc> },);
 
     // rebuild the struct fields
     let params = fields.iter().map(|f| {
         let name = &f.ident;
         let ty = &f.ty;
-        let attrs = &f.attrs;
+        let attrs = f.attrs.iter().filter(|a| !a.path().is_ident("override_default",),);
+        // FromRow and JSON keys follow #[column] renames too, unless the user already renamed the
+        // field for that derive
+        let column = f
+            .attrs
+            .iter()
+            .find(|a| a.path().is_ident("column",),)
+            .and_then(|a| a.parse_args::<LitStr>().ok(),);
+        let rename =
+            |tool: &str| column.as_ref().filter(|_| !f.attrs.iter().any(|a| is_rename(a, tool,),),);
+        let sqlx_rename = rename("sqlx",).map(|column| quote! { #[sqlx(rename = #column)] },);
+        let serde_rename = rename("serde",).map(|column| quote! { #[serde(rename = #column)] },);
         quote! {
             #(#attrs)*
+            #sqlx_rename
+            #serde_rename
             pub #name: #ty
         }
     },);
 
+    // opt-in async helpers, run against the pool with sqlx's FromRow mapping
+    let crud = if crud {
+        quote! {
+            impl #repo_ident {
+                /// `SELECT <all columns> FROM <table> WHERE id = $1`.
+                pub async fn find_by_id(
+                    pool: &sqlx::PgPool,
+                    id: #id_ty,
+                ) -> Result<Option<Self>, sqlx::Error> {
+                    let sql = format!("SELECT {} FROM {} WHERE id = $1", Field::All, Self::TABLE);
+                    sqlx::query_as::<_, Self>(&sql).bind(id).fetch_optional(pool).await
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let executable = if executable {
+        // #[from_context] columns are bound after the row's own, in insert_sql's order
+        let (ctx_param, ctx_arg,) = context_param(&ast,);
+        let bind_ctx = match ctx_arg.is_empty() {
+            true => quote! {},
+            false => quote! { mae::repo::__private__::BindArgs::bind(#ctx_arg, &mut args); },
+        };
+        quote! {
+            impl InsertRow {
+                /// `INSERT ... RETURNING <all columns>` for this row, bound with its `BindArgs`, so
+                /// unset `#[default]` columns are left to the database as in `insert_sql`.
+                pub async fn execute(
+                    &self #ctx_param,
+                    pool: &sqlx::PgPool,
+                ) -> sqlx::Result<#repo_ident> {
+                    let sql = format!(
+                        "{} {}",
+                        self.insert_sql(#ctx_arg),
+                        #repo_ident::returning_clause()
+                    );
+                    let mut args = sqlx::postgres::PgArguments::default();
+                    mae::repo::__private__::BindArgs::bind(self, &mut args);
+                    #bind_ctx
+                    sqlx::query_as_with::<_, #repo_ident, _>(&sql, args).fetch_one(pool).await
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // the bounds `Build` and `run_app` put on the context, checked here so a bad `Ctx` errors at
+    // the macro's first argument instead of inside mae
+    let ctx_bounds = quote::quote_spanned! {ctx.span()=>
+        const _: fn() = || {
+            fn assert_context<C: Clone + Send + 'static>() {}
+            assert_context::<#ctx>();
+        };
+    };
+
     // rebuild repo struct with the existing fields and default fields for the repo
     // NOTE: here, we are deriving the Repo with the proc_macro_derive fn from above
     let repo = quote! {
 
         #(#repo_attrs)*
         #[derive(mae_macros::MaeRepo, Debug, sqlx::FromRow, serde::Serialize, serde::Deserialize, Clone)]
+        #[table(#schema)]
+        #[context(#ctx)]
+        #(#repo_helper_attrs)*
         pub struct #repo_ident {
-            #[locked]
-            pub id: i32,
-            #[insert_only]
-            pub sys_client: i32,
-            pub status: mae::repo::default::DomainStatus,
+            #id
+            #sys_client
+            #status
             #(#params,)*
-            pub comment: Option<String>,
-            #[sqlx(json)]
-            pub tags: serde_json::Value,
-            #[sqlx(json)]
-            pub sys_detail: serde_json::Value,
-            #[locked]
-            pub created_by: i32,
-            #[locked]
-            pub updated_by: i32,
-            #[locked]
-            pub created_at: chrono::DateTime<chrono::Utc>,
-            #[locked]
-            pub updated_at: chrono::DateTime<chrono::Utc>,
+            #comment
+            #tags
+            #sys_detail
+            #created_by
+            #updated_by
+            #created_at
+            #updated_at
         }
+        #ctx_bounds
         impl mae::repo::__private__::Build<#ctx, InsertRow, UpdateRow, Field, PatchField> for #repo_ident {
             fn schema() -> String {
                 #schema.to_string()
             }
         }
+        impl #repo_ident {
+            /// The Postgres schema (if any) and the table, e.g. `(Some("public"), "users")`.
+            pub fn schema_parts() -> (Option<&'static str>, &'static str) {
+                #schema_parts
+            }
+
+            /// The row as json, via its derived `Serialize`.
+            pub fn into_json(&self) -> Result<serde_json::Value, serde_json::Error> {
+                serde_json::to_value(self)
+            }
+
+            /// A row from json, via its derived `Deserialize`.
+            pub fn from_json(v: serde_json::Value) -> Result<Self, serde_json::Error> {
+                serde_json::from_value(v)
+            }
+        }
+        #crud
+        #executable
     };
-    repo.into()
+    repo
 }
 
-#[proc_macro_derive(MaeRepo, attributes(from_context, insert_only, update_only, locked))]
+#[proc_macro_derive(
+    MaeRepo,
+    attributes(
+        from_context,
+        insert_only,
+        update_only,
+        locked,
+        readonly,
+        unique,
+        column,
+        default,
+        nullable,
+        skip,
+        table,
+        context,
+        row_derive,
+        arbitrary,
+        validate,
+        enum_column
+    )
+)]
 pub fn derive_mae_repo(item: TokenStream,) -> TokenStream {
     let ast = parse_macro_input!(item as DeriveInput);
 
-    // Making sure it the derive macro is called on a struct;
-    let _ = match &ast.data {
-        Struct(DataStruct { fields: Fields::Named(fields,), .. },) => &fields.named,
-        _ => {
-            return syn::Error::new_spanned(
-                &ast.ident,
-                "MaeRepo derive expects a struct with named fields",
-            )
-            .to_compile_error()
-            .into();
-        }
-    };
+    // One precise error up front instead of one per generator.
+    if let Err(e,) = validate_named_struct(&ast,) {
+        return e.to_compile_error().into();
+    }
+
+    // #[skip] and PhantomData fields stay on the struct but aren't columns; only arbitrary() still
+    // fills them
+    let full_ast = ast;
+    let ast = readonly_as_locked(without_skipped(&full_ast,),);
+
+    if let Err(e,) = validate_field_attrs(&ast,) {
+        return e.to_compile_error().into();
+    }
 
-    let (insert_row, _,) = to_row(&ast, vec!["locked".into(), "update_only".into()],);
-    let (update_row, _,) = to_row(&ast, vec!["locked".into(), "insert_only".into()],);
+    let (insert_row, _,) =
+        to_row(&ast, vec!["locked".into(), "update_only".into(), "from_context".into()],);
+    let (update_row, _,) =
+        to_row(&ast, vec!["locked".into(), "insert_only".into(), "from_context".into()],);
+    let (context_row, _,) = to_context(&ast,);
     let (repo_typed, _,) = to_patches(&ast,);
     let (repo_variant, _,) = to_fields(&ast,);
+    let (tenant_guard, _,) = to_tenant(&ast,);
+    let (touch, _,) = to_touch(&ast,);
+    let array_checks = to_array_checks(&ast,);
+    let json_accessors = to_json_accessors(&ast,);
+    let enum_columns = to_enum_columns(&ast,);
+    let conflict = to_conflict(&ast,);
+    let status = to_status(&ast,);
+    let (filter, _,) = to_filter(&ast,);
+    let table = to_table(&ast,);
+    let reset_audit = to_reset_audit(&ast,);
+    let pk = to_pk(&ast,);
+    let (validate, _,) = to_validate(&ast,);
+    let arbitrary = to_arbitrary(&full_ast,);
 
     quote! {
         #repo_variant
         #insert_row
         #update_row
+        #context_row
         #repo_typed
+        #tenant_guard
+        #touch
+        #array_checks
+        #json_accessors
+        #enum_columns
+        #conflict
+        #status
+        #filter
+        #table
+        #reset_audit
+        #pk
+        #validate
+        #arbitrary
     }
     .into()
 }
 
+/// Options accepted by `#[mae_test(...)]`.
+#[derive(Default,)]
+struct TestArgs {
+    /// Run the test body inside a `tracing::info_span!` named after the test.
+    span: bool,
+    /// Clone a fresh database from a template for this test and drop it afterwards.
+    fresh_db: bool,
+    /// Seed the test RNG; `seed_value` pins it, otherwise it is drawn from entropy.
+    seed: bool,
+    seed_value: Option<syn::LitInt,>,
+    /// Install a stderr `tracing_subscriber` at this level for the body only.
+    log: Option<LitStr,>,
+    /// Teardown functions run in order after the body; empty means the default teardown.
+    teardown: Vec<syn::Path,>,
+    /// Skip teardown when the body panics, leaving its state behind for inspection.
+    keep_on_fail: bool,
+    /// Record name, duration and outcome with this fn, `crate::common::metrics::record_metric` for
+    /// a bare `metrics`.
+    metrics: Option<syn::Path,>,
+    /// Run this fn before the body and bind its result as `setup`, `crate::common::context::setup`
+    /// for a bare `setup`.
+    setup: Option<syn::Path,>,
+    /// Never run teardown, pass or fail, leaving the test's state behind.
+    no_teardown: bool,
+    /// Return a body panic as `Err` instead of re-raising it.
+    catch: bool,
+    /// Try to install a global `tracing_subscriber` writing through libtest's output capture.
+    trace: bool,
+    /// Drive setup, body and teardown inside one `tokio::task::LocalSet` on a current-thread
+    /// runtime, so `!Send` futures can be spawned.
+    local: bool,
+    /// Build a current-thread runtime instead of the default multi-thread one.
+    current_thread: bool,
+    /// Worker count for the multi-thread runtime; tokio's default when unset.
+    worker_threads: Option<syn::LitInt,>,
+    /// Fail the body once it has run this many milliseconds.
+    timeout_ms: Option<syn::LitInt,>,
+}
+
+impl Parse for TestArgs {
+    fn parse(input: ParseStream<'_,>,) -> syn::Result<Self,> {
+        let mut args = Self::default();
+        let metas = Punctuated::<Meta, Token![,],>::parse_terminated(input,)?;
+        for meta in metas {
+            match &meta {
+                Meta::Path(p,) if p.is_ident("span",) => args.span = true,
+                Meta::Path(p,) if p.is_ident("fresh_db",) => args.fresh_db = true,
+                Meta::Path(p,) if p.is_ident("seed",) => args.seed = true,
+                Meta::Path(p,) if p.is_ident("keep_on_fail",) => args.keep_on_fail = true,
+                Meta::Path(p,) if p.is_ident("metrics",) => {
+                    args.metrics = Some(syn::parse_quote!(crate::common::metrics::record_metric),)
+                }
+                Meta::Path(p,) if p.is_ident("setup",) => {
+                    args.setup = Some(syn::parse_quote!(crate::common::context::setup),)
+                }
+                Meta::Path(p,) if p.is_ident("no_teardown",) => args.no_teardown = true,
+                Meta::Path(p,) if p.is_ident("catch",) => args.catch = true,
+                Meta::Path(p,) if p.is_ident("local",) => args.local = true,
+                Meta::Path(p,) if p.is_ident("trace",) => args.trace = true,
+                Meta::NameValue(nv,) if nv.path.is_ident("teardown",) => {
+                    // a bare path, or the same path quoted, e.g. "crate::fixtures::cleanup"
+                    let to_path = |e: &syn::Expr| {
+                        match e {
+                        syn::Expr::Path(p,) => Ok(p.path.clone(),),
+                        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit,), .. },) => {
+                            lit.parse::<syn::Path>().map_err(|_| {
+                                syn::Error::new_spanned(
+                                    lit,
+                                    "expected a teardown fn path, e.g. teardown = \"crate::fixtures::cleanup\"",
+                                )
+                            },)
+                        }
+                        _ => Err(syn::Error::new_spanned(e, "expected a teardown fn path",),),
+                    }
+                    };
+                    let paths = match &nv.value {
+                        syn::Expr::Array(a,) => {
+                            a.elems.iter().map(to_path,).collect::<syn::Result<Vec<_,>,>>()?
+                        }
+                        syn::Expr::Path(_,) | syn::Expr::Lit(_,) => vec![to_path(&nv.value,)?],
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                &nv.value,
+                                "expected a teardown fn path or a list of them, e.g. teardown = [a::clean, b::clean]",
+                            ),);
+                        }
+                    };
+                    args.teardown.extend(paths,);
+                }
+                Meta::NameValue(nv,) if nv.path.is_ident("setup",) => {
+                    let syn::Expr::Path(p,) = &nv.value else {
+                        return Err(syn::Error::new_spanned(
+                            &nv.value,
+                            "expected a setup fn path, e.g. setup = crate::fixtures::seed",
+                        ),);
+                    };
+                    args.setup = Some(p.path.clone(),);
+                }
+                Meta::NameValue(nv,) if nv.path.is_ident("metrics",) => {
+                    let syn::Expr::Path(p,) = &nv.value else {
+                        return Err(syn::Error::new_spanned(
+                            &nv.value,
+                            "expected a recorder fn path, e.g. metrics = crate::harness::record",
+                        ),);
+                    };
+                    args.metrics = Some(p.path.clone(),);
+                }
+                Meta::NameValue(nv,) if nv.path.is_ident("log",) => {
+                    let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit,), .. },) = &nv.value
+                    else {
+                        return Err(syn::Error::new_spanned(
+                            &nv.value,
+                            "expected a level string, e.g. log = \"debug\"",
+                        ),);
+                    };
+                    args.log = Some(lit.clone(),);
+                }
+                Meta::NameValue(nv,) if nv.path.is_ident("seed",) => {
+                    let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(lit,), .. },) = &nv.value
+                    else {
+                        return Err(syn::Error::new_spanned(
+                            &nv.value,
+                            "expected an integer seed, e.g. seed = 12345",
+                        ),);
+                    };
+                    args.seed = true;
+                    args.seed_value = Some(lit.clone(),);
+                }
+                Meta::NameValue(nv,) if nv.path.is_ident("flavor",) => {
+                    let flavor = match &nv.value {
+                        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit,), .. },) => {
+                            lit.value()
+                        }
+                        _ => String::new(),
+                    };
+                    match flavor.as_str() {
+                        "current_thread" => args.current_thread = true,
+                        "multi_thread" => args.current_thread = false,
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                &nv.value,
+                                "expected flavor = \"current_thread\" or \"multi_thread\"",
+                            ),);
+                        }
+                    }
+                }
+                Meta::NameValue(nv,) if nv.path.is_ident("worker_threads",) => {
+                    let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(lit,), .. },) = &nv.value
+                    else {
+                        return Err(syn::Error::new_spanned(
+                            &nv.value,
+                            "expected a thread count, e.g. worker_threads = 2",
+                        ),);
+                    };
+                    args.worker_threads = Some(lit.clone(),);
+                }
+                Meta::NameValue(nv,) if nv.path.is_ident("timeout_ms",) => {
+                    let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(lit,), .. },) = &nv.value
+                    else {
+                        return Err(syn::Error::new_spanned(
+                            &nv.value,
+                            "expected milliseconds, e.g. timeout_ms = 5000",
+                        ),);
+                    };
+                    args.timeout_ms = Some(lit.clone(),);
+                }
+                _ => return Err(syn::Error::new_spanned(meta, "unknown #[mae_test] option",),),
+            }
+        }
+        if let (true, Some(worker_threads,),) =
+            (args.current_thread || args.local, &args.worker_threads,)
+        {
+            return Err(syn::Error::new_spanned(
+                worker_threads,
+                "worker_threads only applies to the multi_thread flavor",
+            ),);
+        }
+        if let (true, Some(log,),) = (args.trace, &args.log,) {
+            return Err(syn::Error::new_spanned(
+                log,
+                "trace and log both install a subscriber; pick one",
+            ),);
+        }
+        if args.no_teardown && (args.keep_on_fail || !args.teardown.is_empty()) {
+            return Err(syn::Error::new(
+                input.span(),
+                "no_teardown can't be combined with teardown or keep_on_fail",
+            ),);
+        }
+        Ok(args,)
+    }
+}
+
+/// Walks a test body for `.unwrap()`/`.expect()` calls and `assert!`/`assert_eq!`/`assert_ne!`
+/// (however they are pathed), erroring at each one. Arguments of other macros are visited too
+/// when they parse as expressions, so `println!("{}", x.unwrap())` is caught.
+#[derive(Default,)]
+struct ForbiddenCalls {
+    errors: Option<syn::Error,>,
+}
+
+impl ForbiddenCalls {
+    const MESSAGE: &'static str = "#[mae_test] forbids assert*/unwrap/expect in test bodies; use must::* helpers or return Result and use `?`";
+
+    fn push(&mut self, e: syn::Error,) {
+        match self.errors.as_mut() {
+            Some(errors,) => errors.combine(e,),
+            None => self.errors = Some(e,),
+        }
+    }
+}
+
+impl<'ast,> syn::visit::Visit<'ast,> for ForbiddenCalls {
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall,) {
+        if node.method == "unwrap" || node.method == "expect" {
+            self.push(syn::Error::new_spanned(node, Self::MESSAGE,),);
+        }
+        syn::visit::visit_expr_method_call(self, node,);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro,) {
+        let is_assert = node.path.segments.last().is_some_and(|s| {
+            s.ident == "assert" || s.ident == "assert_eq" || s.ident == "assert_ne"
+        },);
+        if is_assert {
+            self.push(syn::Error::new_spanned(node, Self::MESSAGE,),);
+            return;
+        }
+        if let Ok(args,) =
+            node.parse_body_with(Punctuated::<syn::Expr, Token![,],>::parse_terminated,)
+        {
+            args.iter().for_each(|arg| self.visit_expr(arg,),);
+        }
+    }
+}
+
 /// Expands:
-/// #[test]
+/// #[mae_test]
 /// async fn foo() { ... }
 ///
-/// into:
-/// #[allow(clippy::disallowed_methods)]
-/// #[tokio::test(flavor = "multi_thread")]
-/// async fn foo() { ... }
+/// into a plain, synchronous `#[test] fn foo()` that:
+/// - builds a tokio runtime (multi-thread unless `flavor` or `local` says otherwise),
+/// - runs setup when asked for,
+/// - runs the body on the runtime under `catch_unwind`, so a failing body still gets its teardown,
+/// - runs teardown (`crate::common::context::teardown` by default),
+/// - then returns the body's result or re-raises the first panic.
+///
+/// The runtime and hooks live in an inner fn under `#[allow(clippy::disallowed_methods)]`. The
+/// body itself is linted instead: `.unwrap()`, `.expect()` and `assert!`/`assert_eq!`/`assert_ne!`
+/// are compile errors there; use the `must_*` helpers, or return `Result` and use `?`.
+///
+/// Options:
+/// - `span`: run the body inside a `tracing::info_span!` named after the test.
+/// - `fresh_db`: before the body, `CREATE DATABASE <name> TEMPLATE <template>` over a superuser
+///   connection and bind the new database's name as `fresh_db: String` in the body. `<name>` is
+///   `mae_<unix nanos>_<test fn>`, cut to Postgres' 63 byte identifier limit. The connection URL
+///   comes from `MAE_TEST_ADMIN_URL` (falling back to `DATABASE_URL`) and the template from
+///   `MAE_TEST_TEMPLATE` (default `mae_test_template`). The database is dropped `WITH (FORCE)`
+///   after teardown, whether or not the test passed.
+/// - `seed` / `seed = N`: bind `rng`, a `rand::rngs::StdRng` seeded with `N` (or with entropy when
+///   no value is given), in the body, e.g. for `Repo::arbitrary(&mut rng)`. The seed is always
+///   written to stderr, so a failing run shows the value to pin.
+/// - `log = "<level>"`: install a `tracing_subscriber` printing to stderr at `trace`, `debug`,
+///   `info`, `warn` or `error` as the thread default while the body runs. It is removed before
+///   teardown, so it never leaks into teardown or other tests; without it the ambient subscriber
+///   is used.
+/// - `teardown = path` / `teardown = [a::clean, b::clean]`: async fns run after the body instead
+///   of `crate::common::context::teardown`, in the declared order. Paths may also be given as
+///   strings, e.g. `teardown = "crate::fixtures::cleanup"`. Every one of them runs even if
+///   an earlier one panics; the first teardown panic is the one re-raised.
+/// - `keep_on_fail`: debugging aid that only runs teardown (and drops a `fresh_db`) when the body
+///   passed. A failing body leaves its rows behind and its panic is re-raised as usual. Off by
+///   default since it leaves dirty state for the following tests.
+/// - `metrics` / `metrics = path`: after teardown, call
+///   `path(name: &'static str, elapsed: Duration, passed: bool)` with the test name, wall time
+///   (body + teardown) and whether it passed (honouring `#[should_panic]`). `path` defaults to
+///   `crate::common::metrics::record_metric`, next to the default teardown. Tests run in
+///   parallel, so the recorder has to be thread-safe, e.g. push onto a process-global
+///   `OnceLock<Mutex<Vec<_>>>` that a `print_metrics()` dumps at the end.
+/// - `flavor = "current_thread"` / `flavor = "multi_thread"`: the tokio runtime the test runs
+///   on, multi-thread by default. `worker_threads = N` sizes the multi-thread runtime.
+/// - `timeout_ms = N`: panic with the test name and `N` when the body is still running after `N`
+///   ms (via `tokio::time::timeout`). Teardown still runs and isn't subject to the deadline.
+/// - `setup` / `setup = path`: run `path().await` (default `crate::common::context::setup`)
+///   before the body and bind its return value as `setup` there, e.g. for seeded ids. When setup
+///   panics the body is skipped, teardown still runs and the setup panic is re-raised. Off by
+///   default, so nothing is bound and no setup fn is required.
+/// - `no_teardown`: debugging aid that never runs teardown nor drops a `fresh_db`, so the state
+///   of a failing test can be inspected afterwards. The body's result or panic is passed straight
+///   through. Can't be combined with `teardown = ...` or `keep_on_fail`.
+/// - `catch`: a panic in setup or the body is returned as `Err(E::from(message))` instead of
+///   being re-raised, after teardown has run, e.g. for harnesses looping over cases and
+///   collecting failures. The test must return `Result<_, E>` with `E: From<String>`, and can't
+///   also be `#[should_panic]`. Teardown panics are still re-raised.
+/// - `trace`: `try_init` a global `tracing_subscriber::fmt().with_test_writer()` before setup.
+///   Its output goes through libtest's capture, so the logs of setup, body and teardown are
+///   printed only when the test fails. An already installed global subscriber is left in place.
+///   Can't be combined with `log`.
+/// - `local`: run on a current-thread runtime with setup, body and teardown each driven through
+///   the same `tokio::task::LocalSet::run_until`, so `tokio::task::spawn_local` works for `!Send`
+///   futures (e.g. actix actors). Can't be combined with `worker_threads`.
+///
+/// Teardown relies on `catch_unwind`, so test builds using `panic = "abort"` are rejected at
+/// compile time.
+///
+/// Other attributes on the test (`#[ignore = "..."]`, docs, cfgs), above or below
+/// `#[mae_test]`, are kept on the emitted `#[test]` fn.
+///
+/// `#[should_panic]` is passed through to the outer `#[test]`. Teardown always runs before the
+/// body's panic is re-raised, and a panic coming from teardown alone never satisfies
+/// `#[should_panic]`: it is reported on stderr and the test fails for not panicking.
 #[proc_macro_attribute]
+pub fn mae_test(attr: TokenStream, item: TokenStream,) -> TokenStream {
+    expand_mae_test(attr.into(), item.into(),).into()
+}
+
 #[allow(clippy::replace_box)]
-pub fn mae_test(_attr: TokenStream, item: TokenStream,) -> TokenStream {
-    let mut f = match syn::parse::<syn::ItemFn,>(item,) {
+fn expand_mae_test(
+    attr: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let args = match syn::parse2::<TestArgs,>(attr,) {
+        Ok(args,) => args,
+        Err(e,) => return e.to_compile_error(),
+    };
+    let mut f = match syn::parse2::<syn::ItemFn,>(item,) {
         Ok(f,) => f,
         Err(_,) => {
             return syn::Error::new(
                 proc_macro2::Span::call_site(),
                 "#[mae_test] can only be applied to a function",
             )
-            .to_compile_error()
-            .into();
+            .to_compile_error();
         }
     };
 
+    // Under `local` every future handed to the runtime goes through the same LocalSet.
+    let local = args.local;
+    let in_local = |fut: proc_macro2::TokenStream| match local {
+        true => quote::quote! { __mae_local.run_until(#fut) },
+        false => fut,
+    };
+
     // Tests can't take arguments.
     if !f.sig.inputs.is_empty() {
         return syn::Error::new_spanned(
             &f.sig.inputs,
             "#[mae_test] test functions must not take arguments",
         )
-        .to_compile_error()
-        .into();
+        .to_compile_error();
     }
 
     // Capture original body before rewriting.
     let orig_block = *f.block;
 
     // ---- Enforce: no assert*/unwrap/expect in the user's test body ----
-    // (String-based scan; simple and effective for policy enforcement.)
-    let body_s = quote::quote!(#orig_block).to_string();
-
-    let forbidden = [
-        ".expect",    // Result::expect / Option::expect
-        ".unwrap",    // Result::unwrap / Option::unwrap
-        "assert!",    // assert!
-        "assert_eq!", // assert_eq!
-        "assert_ne!", // assert_ne!
-    ];
-
-    if forbidden.iter().any(|pat| body_s.contains(pat,),) {
-        return syn::Error::new_spanned(
-            &orig_block,
-            "#[mae_test] forbids assert*/unwrap/expect in test bodies; use must::* helpers or return Result and use `?`",
-        )
-        .to_compile_error()
-        .into();
+    let mut forbidden = ForbiddenCalls::default();
+    syn::visit::Visit::visit_block(&mut forbidden, &orig_block,);
+    if let Some(e,) = forbidden.errors {
+        return e.to_compile_error();
     }
 
     // Extract return type as a Type.
@@ -243,6 +956,288 @@ pub fn mae_test(_attr: TokenStream, item: TokenStream,) -> TokenStream {
         syn::ReturnType::Type(_, ty,) => (**ty).clone(),
     };
 
+    // Optionally attribute everything the user body logs to a span named after the test. The
+    // span only wraps the body; teardown runs after it has been exited.
+    let test_name = f.sig.ident.to_string();
+    let user_body = if args.span {
+        quote::quote! {
+            tracing::Instrument::instrument(
+                async move #orig_block,
+                tracing::info_span!(#test_name),
+            )
+            .await
+        }
+    } else {
+        quote::quote! { (async move #orig_block).await }
+    };
+
+    // The deadline covers the body only, not fresh_db creation or teardown.
+    let user_body = match &args.timeout_ms {
+        Some(ms,) => quote::quote! {
+            match tokio::time::timeout(
+                std::time::Duration::from_millis(#ms),
+                async move { #user_body },
+            )
+            .await
+            {
+                Ok(__ret) => __ret,
+                Err(_) => panic!("#[mae_test] {} timed out after {} ms", #test_name, #ms),
+            }
+        },
+        None => user_body,
+    };
+
+    // Per-test database cloned from a template; created inside the body's catch_unwind so a
+    // failed CREATE still runs teardown, dropped after teardown so it can't be in use anymore.
+    let (fresh_db_name, fresh_db_create, fresh_db_drop,) = if args.fresh_db {
+        (
+            quote::quote! {
+                let __mae_fresh_db: String = {
+                    let __nanos = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map(|d| d.as_nanos())
+                        .unwrap_or_default();
+                    let mut __name = format!("mae_{}_{}", __nanos, #test_name);
+                    __name.truncate(63);
+                    __name
+                };
+                let __mae_admin_url = std::env::var("MAE_TEST_ADMIN_URL")
+                    .or_else(|_| std::env::var("DATABASE_URL"))
+                    .unwrap_or_else(|_| {
+                        panic!("#[mae_test(fresh_db)] requires MAE_TEST_ADMIN_URL or DATABASE_URL")
+                    });
+            },
+            quote::quote! {
+                #[allow(unused_variables)]
+                let fresh_db = __mae_fresh_db.clone();
+                {
+                    let __template = std::env::var("MAE_TEST_TEMPLATE")
+                        .unwrap_or_else(|_| "mae_test_template".to_string());
+                    let mut __conn =
+                        <sqlx::PgConnection as sqlx::Connection>::connect(&__mae_admin_url)
+                            .await
+                            .unwrap_or_else(|e| panic!("#[mae_test(fresh_db)] failed to connect as admin: {:?}", e));
+                    let __sql = format!("CREATE DATABASE \"{}\" TEMPLATE \"{}\"", fresh_db, __template);
+                    sqlx::query(__sql.as_str())
+                        .execute(&mut __conn)
+                        .await
+                        .unwrap_or_else(|e| panic!("#[mae_test(fresh_db)] failed to create database from template: {:?}", e));
+                }
+            },
+            quote::quote! {
+                // Dropped even when teardown panicked; a failed drop counts as a teardown failure.
+                let __drop_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    __mae_rt.block_on(async {
+                        let mut __conn =
+                            <sqlx::PgConnection as sqlx::Connection>::connect(&__mae_admin_url)
+                                .await
+                                .unwrap_or_else(|e| panic!("#[mae_test(fresh_db)] failed to connect as admin: {:?}", e));
+                        let __sql =
+                            format!("DROP DATABASE IF EXISTS \"{}\" WITH (FORCE)", __mae_fresh_db);
+                        sqlx::query(__sql.as_str())
+                            .execute(&mut __conn)
+                            .await
+                            .unwrap_or_else(|e| panic!("#[mae_test(fresh_db)] failed to drop database: {:?}", e));
+                    })
+                }));
+                __teardown_result = __teardown_result.and(__drop_result);
+            },
+        )
+    } else {
+        (quote::quote! {}, quote::quote! {}, quote::quote! {},)
+    };
+
+    // Thread-default subscriber, held only for the body's block_on.
+    let log_guard = match &args.log {
+        Some(level,) => {
+            let level = match level.value().to_ascii_lowercase().as_str() {
+                "trace" => quote::quote! { tracing::Level::TRACE },
+                "debug" => quote::quote! { tracing::Level::DEBUG },
+                "info" => quote::quote! { tracing::Level::INFO },
+                "warn" => quote::quote! { tracing::Level::WARN },
+                "error" => quote::quote! { tracing::Level::ERROR },
+                _ => {
+                    return syn::Error::new_spanned(
+                        level,
+                        "expected one of \"trace\", \"debug\", \"info\", \"warn\", \"error\"",
+                    )
+                    .to_compile_error();
+                }
+            };
+            quote::quote! {
+                let __mae_log_guard = tracing::subscriber::set_default(
+                    tracing_subscriber::fmt()
+                        .with_max_level(#level)
+                        .with_writer(std::io::stderr)
+                        .finish(),
+                );
+            }
+        }
+        None => quote::quote! {},
+    };
+
+    // The seed is picked and printed up front; the body gets an RNG seeded with it.
+    let (seed, seeded_rng,) = if args.seed {
+        let value = match &args.seed_value {
+            Some(value,) => quote::quote! { #value },
+            None => quote::quote! {
+                std::hash::BuildHasher::hash_one(
+                    &std::collections::hash_map::RandomState::new(),
+                    std::time::SystemTime::now(),
+                )
+            },
+        };
+        (
+            quote::quote! {
+                let __mae_seed: u64 = #value;
+                eprintln!("#[mae_test] rng seed = {0} (pin it with #[mae_test(seed = {0})])", __mae_seed);
+            },
+            quote::quote! {
+                #[allow(unused_mut, unused_variables)]
+                let mut rng = <rand::rngs::StdRng as rand::SeedableRng>::seed_from_u64(__mae_seed);
+            },
+        )
+    } else {
+        (quote::quote! {}, quote::quote! {},)
+    };
+
+    // `#[should_panic]` stays on the outer fn; we only need to know about it so a teardown panic
+    // isn't mistaken for the expected one.
+    let should_panic = f.attrs.iter().any(|a| a.path().is_ident("should_panic",),);
+
+    let user_panic = if args.catch {
+        let returns_result = matches!(
+            &ret_ty,
+            syn::Type::Path(p) if p.path.segments.last().is_some_and(|s| s.ident == "Result")
+        );
+        if !returns_result {
+            return syn::Error::new_spanned(
+                &f.sig,
+                "#[mae_test(catch)] needs the test to return Result<_, E> with E: From<String>",
+            )
+            .to_compile_error();
+        }
+        if should_panic {
+            return syn::Error::new_spanned(
+                &f.sig,
+                "#[mae_test(catch)] returns panics as Err, so it can't be #[should_panic]",
+            )
+            .to_compile_error();
+        }
+        quote::quote! {{
+            let __msg = match __panic.downcast::<String>() {
+                Ok(__msg) => *__msg,
+                Err(__panic) => match __panic.downcast::<&'static str>() {
+                    Ok(__msg) => __msg.to_string(),
+                    Err(_) => "non-string panic payload".to_string(),
+                },
+            };
+            Err(::core::convert::From::from(__msg))
+        }}
+    } else {
+        quote::quote! { std::panic::resume_unwind(__panic) }
+    };
+
+    let metrics = if let Some(record,) = &args.metrics {
+        quote::quote! {
+            let __mae_passed = match (&__user_result, &__teardown_result) {
+                (Ok(_), Ok(())) => !#should_panic,
+                (Err(_), _) => #should_panic,
+                (Ok(_), Err(_)) => false,
+            };
+            #record(#test_name, __mae_started.elapsed(), __mae_passed);
+        }
+    } else {
+        quote::quote! {}
+    };
+
+    // Setup gets its own catch_unwind so a panic there skips the body but not teardown.
+    let (setup, setup_bind,) = match &args.setup {
+        Some(path,) => {
+            let setup_fut = in_local(quote::quote! { async { #path().await } },);
+            (
+                quote::quote! {
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        __mae_rt.block_on(#setup_fut)
+                    }))
+                },
+                quote::quote! {
+                    #[allow(unused_variables)]
+                    let setup = __mae_setup;
+                },
+            )
+        }
+        None => (quote::quote! { std::thread::Result::Ok(()) }, quote::quote! {},),
+    };
+
+    let runtime_builder = match (args.current_thread || args.local, &args.worker_threads,) {
+        (true, _,) => quote::quote! { tokio::runtime::Builder::new_current_thread() },
+        (false, Some(n,),) => {
+            quote::quote! { tokio::runtime::Builder::new_multi_thread().worker_threads(#n) }
+        }
+        (false, None,) => quote::quote! { tokio::runtime::Builder::new_multi_thread() },
+    };
+
+    let keep_on_fail = args.keep_on_fail;
+    let teardown = if args.teardown.is_empty() {
+        vec![syn::parse_quote!(crate::common::context::teardown)]
+    } else {
+        args.teardown
+    };
+
+    let teardown_futs =
+        teardown.iter().map(|t| in_local(quote::quote! { async { #t().await; } },),);
+    let run_teardown = if args.no_teardown {
+        let keep_fresh_db = if args.fresh_db {
+            quote::quote! {
+                let _ = __mae_admin_url;
+                eprintln!("#[mae_test(no_teardown)] kept database {}", __mae_fresh_db);
+            }
+        } else {
+            quote::quote! {}
+        };
+        quote::quote! {
+            let __teardown_result: std::thread::Result<()> = Ok(());
+            #keep_fresh_db
+        }
+    } else {
+        quote::quote! {
+            // Always attempt teardown, even if the user body panicked (unless keep_on_fail).
+            let mut __teardown_result = Ok(());
+            if !#keep_on_fail || __user_result.is_ok() {
+                #(
+                    let __result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        __mae_rt.block_on(#teardown_futs)
+                    }));
+                    __teardown_result = __teardown_result.and(__result);
+                )*
+                #fresh_db_drop
+            } else {
+                eprintln!("#[mae_test(keep_on_fail)] test failed; skipped teardown to keep its state");
+            }
+        }
+    };
+
+    let body_fut = in_local(quote::quote! {
+        async {
+            #setup_bind
+            #seeded_rng
+            #fresh_db_create
+            // run user test body
+            #user_body
+        }
+    },);
+    let trace_init = if args.trace {
+        quote::quote! { let _ = tracing_subscriber::fmt().with_test_writer().try_init(); }
+    } else {
+        quote::quote! {}
+    };
+    let local_set = if args.local {
+        quote::quote! { let __mae_local = tokio::task::LocalSet::new(); }
+    } else {
+        quote::quote! {}
+    };
+
     // Ensure the outer test function is synchronous; we drive an async block ourselves.
     f.sig.asyncness = None;
 
@@ -252,43 +1247,497 @@ pub fn mae_test(_attr: TokenStream, item: TokenStream,) -> TokenStream {
 
     // Generate body: inner helper has the clippy allow, and ONLY contains runtime + teardown.
     f.block = Box::new(syn::parse_quote!({
+        // catch_unwind can't catch anything under panic = "abort", so teardown would silently
+        // never run after a failure.
+        #[cfg(panic = "abort")]
+        compile_error!(
+            "#[mae_test] requires panic = \"unwind\": teardown after a failing test relies on catch_unwind"
+        );
+
         #[allow(clippy::disallowed_methods)]
         fn __mae_run_test() -> #ret_ty {
-            let __mae_rt = tokio::runtime::Builder::new_multi_thread()
+            let __mae_rt = #runtime_builder
                 .enable_all()
                 .build()
-                .expect("failed to build tokio runtime for #[mae_test]");
-
-            let __user_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                __mae_rt.block_on(async move {
-                    // run user test body
-                    (async move #orig_block).await
-                })
-            }));
-
-            // Always attempt teardown, even if the user body panicked.
-            let __teardown_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                __mae_rt.block_on(async move {
-                    crate::common::context::teardown().await;
-                })
-            }));
+                .unwrap_or_else(|e| panic!("failed to build tokio runtime for #[mae_test]: {}", e));
+            #local_set
+            #trace_init
+            #fresh_db_name
+            #seed
+
+            let __mae_started = std::time::Instant::now();
+            let __user_result = #setup.and_then(|__mae_setup| {
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    #log_guard
+                    __mae_rt.block_on(#body_fut)
+                }))
+            });
+
+            #run_teardown
+
+            #metrics
 
             match (__user_result, __teardown_result) {
                 (Ok(__ret), Ok(())) => __ret,
 
                 // User panicked; teardown succeeded -> rethrow original panic
-                (Err(__panic), Ok(())) => std::panic::resume_unwind(__panic),
+                (Err(__panic), Ok(())) => #user_panic,
+
+                // User succeeded; teardown panicked under #[should_panic] -> don't let the
+                // teardown panic pass for the expected one
+                (Ok(__ret), Err(_teardown_panic)) if #should_panic => {
+                    eprintln!("#[mae_test] teardown panicked; not counted as the expected panic");
+                    __ret
+                }
 
                 // User succeeded; teardown panicked -> surface teardown panic
                 (Ok(_), Err(__panic)) => std::panic::resume_unwind(__panic),
 
                 // Both panicked -> prefer original user panic (teardown panic would mask test failure)
-                (Err(__panic), Err(_teardown_panic)) => std::panic::resume_unwind(__panic),
+                (Err(__panic), Err(_teardown_panic)) => #user_panic,
             }
         }
 
         __mae_run_test()
     }),);
 
-    TokenStream::from(quote::quote!(#f),)
+    quote::quote!(#f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mae_repo_rejects_unsupported_kinds() {
+        for (item, kind,) in [
+            (quote! { struct T(i32); }, "tuple struct",),
+            (quote! { struct T; }, "unit struct",),
+            (quote! { enum T { A, B } }, "enum",),
+            (quote! { union T { a: i32 } }, "union",),
+        ] {
+            let ast: DeriveInput = match syn::parse2(item,) {
+                Ok(ast,) => ast,
+                Err(e,) => panic!("{}", e),
+            };
+            let err = match validate_named_struct(&ast,) {
+                Ok((),) => panic!("{} accepted", kind),
+                Err(e,) => e.to_string(),
+            };
+            assert_eq!(
+                err,
+                format!("MaeRepo supports only structs with named fields; found {}", kind)
+            );
+        }
+    }
+
+    #[test]
+    fn mae_test_expansion_has_no_expect() {
+        for attr in [quote! {}, quote! { fresh_db },] {
+            let expanded = expand_mae_test(attr, quote! { async fn t() {} },).to_string();
+            assert!(!expanded.contains(". expect (",), "{}", expanded);
+        }
+    }
+
+    #[test]
+    fn teardown_path_defaults_and_overrides() {
+        let body = quote! { async fn t() {} };
+        let default = expand_mae_test(quote! {}, body.clone(),).to_string();
+        assert!(default.contains("crate :: common :: context :: teardown ()"), "{}", default);
+
+        for attr in [
+            quote! { teardown = "crate::fixtures::cleanup" },
+            quote! { teardown = crate::fixtures::cleanup },
+        ] {
+            let overridden = expand_mae_test(attr, body.clone(),).to_string();
+            assert!(overridden.contains("crate :: fixtures :: cleanup ()"), "{}", overridden);
+            assert!(!overridden.contains("context :: teardown"), "{}", overridden);
+        }
+
+        let invalid = expand_mae_test(quote! { teardown = "not a path" }, body,).to_string();
+        assert!(invalid.contains("compile_error"), "{}", invalid);
+        assert!(invalid.contains("expected a teardown fn path"), "{}", invalid);
+    }
+
+    #[test]
+    fn lint_rejects_pathed_asserts() {
+        for body in [
+            quote! { async fn t() { std::assert_eq!(1, 1); } },
+            quote! { async fn t() { core::assert!(true); } },
+            quote! { async fn t() { ::std::assert_ne!(1, 2); } },
+        ] {
+            let expanded = expand_mae_test(quote! {}, body,).to_string();
+            assert!(expanded.contains("compile_error"), "{}", expanded);
+            assert!(expanded.contains("forbids assert*/unwrap/expect"), "{}", expanded);
+        }
+    }
+
+    #[test]
+    fn schema_keeps_user_declared_default_fields() {
+        let expanded = expand_schema(
+            quote! { Ctx, "notes" },
+            quote! { struct Note { #[override_default] pub comment: String, pub body: String } },
+        );
+        let file: syn::File = match syn::parse2(expanded,) {
+            Ok(file,) => file,
+            Err(e,) => panic!("{}", e),
+        };
+        let Some(syn::Item::Struct(syn::ItemStruct {
+            fields: Named(FieldsNamed { named, .. },),
+            ..
+        },),) = file.items.into_iter().next()
+        else {
+            panic!("expected the repo struct first");
+        };
+        let comments = named
+            .iter()
+            .filter(|f| f.ident.as_ref().is_some_and(|i| i == "comment",),)
+            .map(|f| {
+                let ty = &f.ty;
+                quote! { #ty }.to_string()
+            },)
+            .collect::<Vec<_,>>();
+        assert_eq!(comments, vec!["String"]);
+        // the other defaults are still injected
+        assert!(named.iter().any(|f| f.ident.as_ref().is_some_and(|i| i == "tags",)));
+    }
+
+    #[test]
+    fn schema_parts_split_namespace() {
+        for (schema, parts,) in [
+            ("users", "(None , \"users\")",),
+            ("public.users", "(Some (\"public\") , \"users\")",),
+        ] {
+            let expanded = expand_schema(quote! { Ctx, #schema }, quote! { struct U {} },);
+            assert!(expanded.to_string().contains(parts), "{}", expanded);
+        }
+
+        for schema in ["a.b.c", ".users", "public.",] {
+            let expanded = expand_schema(quote! { Ctx, #schema }, quote! { struct U {} },);
+            assert!(
+                expanded.to_string().contains("expected a table name or `schema.table`"),
+                "{}",
+                expanded
+            );
+        }
+    }
+
+    #[test]
+    fn catch_requires_result_return() {
+        let expanded = expand_mae_test(quote! { catch }, quote! { async fn t() {} },).to_string();
+        assert!(expanded.contains("needs the test to return Result"), "{}", expanded);
+    }
+
+    #[test]
+    fn schema_rejects_undeclared_overrides_of_injected_fields() {
+        for field in ["id", "created_at",] {
+            let field = Ident::new(field, proc_macro2::Span::call_site(),);
+            let expanded = expand_schema(
+                quote! { Ctx, "notes" },
+                quote! { struct Note { pub #field: i32, pub body: String } },
+            )
+            .to_string();
+            let message = format!(
+                "field `{}` is managed by #[schema] and cannot be declared manually",
+                field
+            );
+            assert!(expanded.contains(&message), "{}", expanded);
+        }
+
+        let expanded = expand_schema(
+            quote! { Ctx, "notes" },
+            quote! { struct Note { #[override_default] pub id: i64 } },
+        )
+        .to_string();
+        assert!(!expanded.contains("compile_error"), "{}", expanded);
+        assert!(!expanded.contains("override_default"), "{}", expanded);
+    }
+
+    #[test]
+    fn schema_mirrors_column_renames_for_serde_and_sqlx() {
+        let expanded = expand_schema(
+            quote! { Ctx, "users" },
+            quote! {
+                struct User {
+                    #[column("user_type")]
+                    pub kind: String,
+                    #[column("display")]
+                    #[serde(rename = "displayName", default)]
+                    pub name: String,
+                    #[serde(default)]
+                    pub bio: String,
+                    #[column("login")]
+                    #[sqlx(rename = "login")]
+                    pub handle: String,
+                }
+            },
+        )
+        .to_string();
+        assert!(
+            expanded.contains(
+                "# [sqlx (rename = \"user_type\")] # [serde (rename = \"user_type\")] pub kind : String"
+            ),
+            "{}",
+            expanded
+        );
+        // the user's own serde rename wins and nothing is added next to it; FromRow still needs
+        // the column name
+        assert!(
+            expanded.contains(
+                "# [serde (rename = \"displayName\" , default)] # [sqlx (rename = \"display\")] pub name : String"
+            ),
+            "{}",
+            expanded
+        );
+        assert!(!expanded.contains("serde (rename = \"display\")"), "{}", expanded);
+        assert_eq!(expanded.matches("sqlx (rename = \"login\")").count(), 1, "{}", expanded);
+        assert!(expanded.contains("# [serde (default)] pub bio : String"), "{}", expanded);
+    }
+
+    #[test]
+    fn sqlx_rename_names_the_column() {
+        let ast: DeriveInput = match syn::parse2(quote! {
+            struct User {
+                #[locked]
+                pub id: i32,
+                #[sqlx(rename = "user_name")]
+                pub name: String,
+            }
+        },)
+        {
+            Ok(ast,) => ast,
+            Err(e,) => panic!("{}", e),
+        };
+        let (fields, _,) = to_fields(&ast,);
+        let fields = fields.to_string();
+        assert!(fields.contains("Field :: name => \"user_name\" . to_string ()"), "{}", fields);
+        let (insert, _,) = to_row(&ast, vec!["locked".into(), "update_only".into()],);
+        assert!(insert.to_string().contains("format ! (\"{}\" , \"user_name\")"), "{}", insert);
+
+        let clash: DeriveInput = match syn::parse2(quote! {
+            struct User {
+                #[column("login")]
+                #[sqlx(rename = "user_name")]
+                pub name: String,
+            }
+        },)
+        {
+            Ok(ast,) => ast,
+            Err(e,) => panic!("{}", e),
+        };
+        assert!(validate_field_attrs(&clash).is_err());
+    }
+
+    #[test]
+    fn run_app_extra_adds_param_and_app_data() {
+        let body = quote! { fn routes() { service("/health") } };
+        let plain = expand_run_app(quote! {}, body.clone(),).to_string();
+        assert!(!plain.contains("Extra"), "{}", plain);
+
+        let extra = expand_run_app(quote! { extra }, body,).to_string();
+        assert!(
+            extra.contains("Context : Clone + Send + 'static , Extra : Clone + Send + 'static"),
+            "{}",
+            extra
+        );
+        assert!(extra.contains("custom_context : Context , extra : Extra ,"), "{}", extra);
+        assert!(
+            extra.contains(
+                ". app_data (web :: Data :: new (custom_context . clone ())) . app_data (web :: Data :: new (extra . clone ()))"
+            ),
+            "{}",
+            extra
+        );
+    }
+
+    #[test]
+    fn schema_names_unsupported_kinds() {
+        for (item, kind,) in [
+            (quote! { struct T(i32); }, "tuple struct",),
+            (quote! { struct T; }, "unit struct",),
+            (quote! { enum T { A, B } }, "enum",),
+            (quote! { union T { a: i32 } }, "union",),
+        ] {
+            let expanded = expand_schema(quote! { Ctx, "t" }, item,).to_string();
+            let message = format!("schema expected a struct with named fields, found {}", kind);
+            assert!(expanded.contains(&message), "{}", expanded);
+        }
+    }
+
+    fn idents(tokens: proc_macro2::TokenStream, out: &mut Vec<String,>,) {
+        for token in tokens {
+            match token {
+                proc_macro2::TokenTree::Ident(i,) => out.push(i.to_string(),),
+                proc_macro2::TokenTree::Group(g,) => idents(g.stream(), out,),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn no_teardown_expansion_never_references_teardown() {
+        for (attr, references,) in [
+            (quote! {}, true,),
+            (quote! { no_teardown }, false,),
+            (quote! { no_teardown, fresh_db }, false,),
+        ] {
+            let mut found = vec![];
+            idents(expand_mae_test(attr, quote! { async fn t() {} },), &mut found,);
+            assert_eq!(found.iter().any(|i| i == "teardown"), references, "{:?}", found);
+        }
+    }
+
+    #[test]
+    fn trace_installs_subscriber_only_when_asked() {
+        for (attr, installs,) in [(quote! {}, false,), (quote! { trace }, true,),] {
+            let mut found = vec![];
+            idents(expand_mae_test(attr, quote! { async fn t() {} },), &mut found,);
+            assert_eq!(found.iter().any(|i| i == "tracing_subscriber"), installs, "{:?}", found);
+        }
+
+        let expanded =
+            expand_mae_test(quote! { trace, log = "debug" }, quote! { async fn t() {} },);
+        assert!(expanded.to_string().contains("trace and log both install a subscriber"));
+    }
+
+    #[test]
+    fn schema_asserts_context_bounds() {
+        let expanded = expand_schema(quote! { Ctx, "t" }, quote! { struct T { a: i32 } },);
+
+        assert!(
+            expanded.to_string().contains(
+                "fn assert_context < C : Clone + Send + 'static > () { } assert_context :: < Ctx > ()"
+            ),
+            "{}",
+            expanded
+        );
+    }
+
+    #[test]
+    fn schema_executable_adds_insert_execute() {
+        let item = quote! { struct Note { body: String } };
+        let plain = expand_schema(quote! { Ctx, "notes" }, item.clone(),).to_string();
+        assert!(!plain.contains("fn execute"), "{}", plain);
+
+        let executable = expand_schema(quote! { Ctx, "notes", executable }, item,).to_string();
+        assert!(
+            executable.contains(
+                "pub async fn execute (& self , pool : & sqlx :: PgPool ,) -> sqlx :: Result < Note >"
+            ),
+            "{}",
+            executable
+        );
+        assert!(executable.contains("Note :: returning_clause ()"), "{}", executable);
+        assert!(executable.contains("query_as_with :: < _ , Note , _ >"), "{}", executable);
+
+        // #[from_context] columns come from a ContextRow, inserted and bound after the row's own
+        let scoped = expand_schema(
+            quote! { Ctx, "notes", executable },
+            quote! { struct Note { #[from_context] pub owner: i32, pub body: String } },
+        )
+        .to_string();
+        assert!(
+            scoped.contains(
+                "pub async fn execute (& self , ctx : & ContextRow , pool : & sqlx :: PgPool ,)"
+            ),
+            "{}",
+            scoped
+        );
+        assert!(scoped.contains("self . insert_sql (ctx)"), "{}", scoped);
+        assert!(
+            scoped.contains(
+                "BindArgs :: bind (self , & mut args) ; mae :: repo :: __private__ :: BindArgs :: bind (ctx , & mut args) ;"
+            ),
+            "{}",
+            scoped
+        );
+    }
+
+    #[test]
+    fn schema_crud_adds_find_by_id() {
+        let item = quote! { struct Note { body: String } };
+        let plain = expand_schema(quote! { Ctx, "notes" }, item.clone(),).to_string();
+        assert!(!plain.contains("fn find_by_id"), "{}", plain);
+
+        let crud = expand_schema(quote! { Ctx, "notes", crud }, item,).to_string();
+        assert!(
+            crud.contains(
+                "pub async fn find_by_id (pool : & sqlx :: PgPool , id : i32 ,) -> Result < Option < Self > , sqlx :: Error >"
+            ),
+            "{}",
+            crud
+        );
+        assert!(
+            crud.contains(
+                "format ! (\"SELECT {} FROM {} WHERE id = $1\" , Field :: All , Self :: TABLE)"
+            ),
+            "{}",
+            crud
+        );
+        assert!(
+            crud.contains(
+                "sqlx :: query_as :: < _ , Self > (& sql) . bind (id) . fetch_optional (pool)"
+            ),
+            "{}",
+            crud
+        );
+    }
+
+    #[test]
+    fn schema_id_type_overrides_injected_id() {
+        let expanded = expand_schema(
+            quote! { Ctx, "notes", crud, id = uuid::Uuid },
+            quote! { struct Note { body: String } },
+        )
+        .to_string();
+        assert!(expanded.contains("pub id : uuid :: Uuid"), "{}", expanded);
+        assert!(!expanded.contains("pub id : i32"), "{}", expanded);
+        assert!(
+            expanded.contains(
+                "pub async fn find_by_id (pool : & sqlx :: PgPool , id : uuid :: Uuid ,) -> Result < Option < Self > , sqlx :: Error >"
+            ),
+            "{}",
+            expanded
+        );
+    }
+
+    #[test]
+    fn schema_adds_json_conversions() {
+        let expanded =
+            expand_schema(quote! { Ctx, "notes" }, quote! { struct Note { body: String } },)
+                .to_string();
+        assert!(
+            expanded.contains(
+                "pub fn into_json (& self) -> Result < serde_json :: Value , serde_json :: Error > { serde_json :: to_value (self) }"
+            ),
+            "{}",
+            expanded
+        );
+        assert!(
+            expanded.contains(
+                "pub fn from_json (v : serde_json :: Value) -> Result < Self , serde_json :: Error > { serde_json :: from_value (v) }"
+            ),
+            "{}",
+            expanded
+        );
+    }
+
+    #[test]
+    fn array_checks_skip_json_columns() {
+        let ast: DeriveInput = match syn::parse2(quote! {
+            struct Recipe {
+                #[sqlx(json)]
+                pub steps: Vec<Step>,
+                pub tools: Vec<Tool>,
+            }
+        },)
+        {
+            Ok(ast,) => ast,
+            Err(e,) => panic!("{}", e),
+        };
+        let checks = to_array_checks(&ast,).to_string();
+
+        assert!(checks.contains("__mae_pg_array_column :: < Tool >"), "{}", checks);
+        assert!(!checks.contains("Step"), "{}", checks);
+    }
 }
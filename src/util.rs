@@ -1,14 +1,11 @@
 use quote::quote;
-use syn::{Data, DataStruct, DeriveInput, Field, Fields, LitStr};
+use syn::{Data, DataStruct, DeriveInput, Field, Fields, LitStr, spanned::Spanned};
 
 type Body = proc_macro2::TokenStream;
 type BodyIdent = proc_macro2::TokenStream;
 
 // TODO:
-// Impl EnumIter for Fields -> this is to generate randomness for tests
-// If there is a flag #[test] at the top of the repo struct to impl a randomness generator
 // From impl:
-// impl Patch -> Field
 // impl Patch => Filter
 // impl Row => Filter
 
@@ -29,6 +26,12 @@ pub fn to_patches(ast: &DeriveInput,) -> (Body, BodyIdent,) {
     let mut typed_enum = vec![];
     let body_ident = quote! { PatchField };
     let mut debug_bindings = vec![];
+    let mut as_patch = vec![];
+    let mut to_field = vec![];
+    let mut apply = vec![];
+    let mut from_json = vec![];
+    let mut columns = vec![];
+    let repo_ident = &ast.ident;
 
     fields.iter().for_each(|f| {
         let name_ident = f.ident.as_ref().ok_or_else(|| {
@@ -38,13 +41,18 @@ pub fn to_patches(ast: &DeriveInput,) -> (Body, BodyIdent,) {
 
         // we need to check if either there are no attrs, or if attr != locked | != insert_only
         if let Ok(name_ident,) = name_ident
-            && f.attrs
-                .iter()
-                .map(|a| !a.path().is_ident("locked",) && !a.path().is_ident("insert_only",),)
-                .all(|a| a == true,)
+            && f.attrs.iter().all(|a| {
+                !a.path().is_ident("locked",)
+                    && !a.path().is_ident("insert_only",)
+                    && !a.path().is_ident("from_context",)
+            },)
         {
             let ty = &f.ty;
-            let name_str = name_ident.to_string();
+            let name_str = column_name(f,);
+
+            as_patch.push(quote! {
+                #body_ident::#name_ident(self.#name_ident.clone())
+            },);
 
             to_arg.push(quote! {
                 #body_ident::#name_ident(arg) => args.add(arg)
@@ -57,10 +65,27 @@ pub fn to_patches(ast: &DeriveInput,) -> (Body, BodyIdent,) {
                 #body_ident::#name_ident(b) => write!(f, "{:?}", b)
             },);
 
+            to_field.push(quote! {
+                #body_ident::#name_ident(_) => Field::#name_ident
+            },);
+
+            apply.push(quote! {
+                #body_ident::#name_ident(v) => self.#name_ident = v.clone()
+            },);
+
+            from_json.push(quote! {
+                #name_str => serde_json::from_value::<#ty>(val)
+                    .map(#body_ident::#name_ident)
+                    .map_err(|source| PatchError::InvalidValue { column: col.to_string(), source })
+            },);
+            columns.push(name_str,);
+
             typed_enum.push(quote! { #name_ident(#ty) },);
         }
     },);
 
+    let bind_consistency = to_bind_consistency(&body_ident,);
+
     let body = quote! {
         #[allow(non_snake_case, non_camel_case_types, nonstandard_style)]
         #[derive(Clone)]
@@ -76,102 +101,1438 @@ pub fn to_patches(ast: &DeriveInput,) -> (Body, BodyIdent,) {
             }
         }
 
-        impl mae::repo::__private__::ToSqlParts for #body_ident {
-            fn to_sql_parts(&self) -> mae::repo::__private__::AsSqlParts {
-                // NOTE: cannot accurately get the bind_idx. Catch it at a higher level
-                (vec![self.to_string()], None)
+        impl #body_ident {
+            /// The column with its `$start_idx` placeholder, plus the index the next bind gets, so
+            /// a sequence of patches can be numbered like `UpdateRow` numbers its columns.
+            pub fn to_sql_parts_at(&self, start_idx: usize) -> (mae::repo::__private__::AsSqlParts, usize) {
+                ((vec![self.to_string()], Some(vec![format!("${}", start_idx)])), start_idx + 1)
+            }
+        }
+
+        impl mae::repo::__private__::ToSqlParts for #body_ident {
+            fn to_sql_parts(&self) -> mae::repo::__private__::AsSqlParts {
+                // a lone patch is the first bind; use to_sql_parts_at when chaining
+                self.to_sql_parts_at(1).0
+            }
+        }
+
+        impl mae::repo::__private__::BindArgs for #body_ident {
+            fn bind(&self, mut args: &mut sqlx::postgres::PgArguments) {
+                let _ = match self {
+                    #(#to_arg,)*
+                };
+            }
+            fn bind_len(&self) -> usize {
+                // NOTE: There will always be one arg for a PatchField
+                1
+            }
+        }
+
+        #bind_consistency
+
+        impl std::fmt::Debug for #body_ident {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                match self {
+                    #(#debug_bindings,)*
+                }
+            }
+        }
+
+        impl From<&#body_ident> for Field {
+            fn from(patch: &#body_ident) -> Self {
+                match patch {
+                    #(#to_field,)*
+                }
+            }
+        }
+
+        impl #repo_ident {
+            /// One patch per patchable column, carrying this row's current values.
+            pub fn as_patch_vec(&self) -> Vec<#body_ident> {
+                vec![#(#as_patch,)*]
+            }
+
+            /// Writes the patch's value onto this row in memory, e.g. to update a cached copy.
+            pub fn apply(&mut self, patch: &#body_ident) {
+                match patch {
+                    #(#apply,)*
+                }
+            }
+        }
+
+        /// Patches applied together, numbered and bound in order.
+        #[derive(Clone, Debug)]
+        pub struct PatchSet(pub Vec<#body_ident>);
+
+        impl PatchSet {
+            /// `SET a = $start_idx, b = $start_idx + 1, ...` plus the index the next bind gets, e.g.
+            /// for a trailing `WHERE id = $n`. Empty when there are no patches.
+            pub fn set_clause(&self, start_idx: usize) -> (String, usize) {
+                let mut idx = start_idx;
+                let mut assignments = vec![];
+                for patch in &self.0 {
+                    let ((columns, placeholders), next) = patch.to_sql_parts_at(idx);
+                    assignments.extend(
+                        columns
+                            .iter()
+                            .zip(placeholders.unwrap_or_default())
+                            .map(|(c, p)| format!("{} = {}", c, p)),
+                    );
+                    idx = next;
+                }
+                match assignments.is_empty() {
+                    true => (String::new(), idx),
+                    false => (format!("SET {}", assignments.join(", ")), idx),
+                }
+            }
+        }
+
+        impl mae::repo::__private__::BindArgs for PatchSet {
+            fn bind(&self, args: &mut sqlx::postgres::PgArguments) {
+                for patch in &self.0 {
+                    patch.bind(args);
+                }
+            }
+            fn bind_len(&self) -> usize {
+                self.0.iter().map(|p| p.bind_len()).sum()
+            }
+        }
+
+        /// Why a json entry couldn't become a patch.
+        #[derive(Debug)]
+        pub enum PatchError {
+            /// No patchable column goes by this name.
+            UnknownColumn(String),
+            /// The value doesn't deserialize into the column's type.
+            InvalidValue { column: String, source: serde_json::Error },
+        }
+
+        impl std::fmt::Display for PatchError {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                match self {
+                    PatchError::UnknownColumn(column) => write!(f, "unknown patch column `{}`", column),
+                    PatchError::InvalidValue { column, source } => {
+                        write!(f, "invalid value for patch column `{}`: {}", column, source)
+                    }
+                }
+            }
+        }
+
+        impl std::error::Error for PatchError {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                match self {
+                    PatchError::UnknownColumn(_) => None,
+                    PatchError::InvalidValue { source, .. } => Some(source),
+                }
+            }
+        }
+
+        impl #body_ident {
+            /// The patch for column `col`, with `val` deserialized into the column's type.
+            pub fn from_json_entry(col: &str, val: serde_json::Value) -> Result<#body_ident, PatchError> {
+                match col {
+                    #(#from_json,)*
+                    _ => Err(PatchError::UnknownColumn(col.to_string())),
+                }
+            }
+        }
+
+        impl TryFrom<std::collections::HashMap<String, serde_json::Value>> for PatchSet {
+            type Error = PatchError;
+
+            /// One patch per entry, in column declaration order so the SQL doesn't depend on the
+            /// map's iteration order.
+            fn try_from(
+                mut entries: std::collections::HashMap<String, serde_json::Value>,
+            ) -> Result<Self, PatchError> {
+                let mut patches = vec![];
+                for col in [#(#columns,)*] {
+                    if let Some(val) = entries.remove(col) {
+                        patches.push(#body_ident::from_json_entry(col, val)?);
+                    }
+                }
+                match entries.into_keys().next() {
+                    Some(col) => Err(PatchError::UnknownColumn(col)),
+                    None => Ok(PatchSet(patches)),
+                }
+            }
+        }
+    };
+    (body, body_ident,)
+}
+
+pub fn to_filter(ast: &DeriveInput,) -> (Body, BodyIdent,) {
+    let body_ident = quote! { Filter };
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) => &fields.named,
+        _ => {
+            return (
+                syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",)
+                    .to_compile_error(),
+                body_ident,
+            );
+        }
+    };
+
+    let mut variants = vec![];
+    let mut predicate_arms = vec![];
+    let mut bind_arms = vec![];
+    let mut debug_arms = vec![];
+
+    for f in fields.iter() {
+        let Some(name,) = f.ident.as_ref() else {
+            continue;
+        };
+        let ty = &f.ty;
+        let name_str = column_name(f,);
+
+        variants.push(quote! { #name(Op, #ty) },);
+        predicate_arms.push(quote! {
+            #body_ident::#name(op, _) => format!("{} {} ${}", #name_str, op, bind_idx)
+        },);
+        bind_arms.push(quote! {
+            #body_ident::#name(_, arg) => args.add(arg)
+        },);
+        debug_arms.push(quote! {
+            #body_ident::#name(op, v) => write!(f, "{} {} {:?}", #name_str, op, v)
+        },);
+    }
+
+    let bind_consistency = to_bind_consistency(&body_ident,);
+
+    let body = quote! {
+        /// Comparison used by a `Filter`.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum Op {
+            Eq,
+            Ne,
+            Lt,
+            Le,
+            Gt,
+            Ge,
+            Like,
+            ILike,
+        }
+
+        impl std::fmt::Display for Op {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{}", match self {
+                    Self::Eq => "=",
+                    Self::Ne => "<>",
+                    Self::Lt => "<",
+                    Self::Le => "<=",
+                    Self::Gt => ">",
+                    Self::Ge => ">=",
+                    Self::Like => "LIKE",
+                    Self::ILike => "ILIKE",
+                })
+            }
+        }
+
+        /// A single-column predicate, e.g. `Filter::status(Op::Eq, DomainStatus::Active)`.
+        #[allow(non_snake_case, non_camel_case_types, nonstandard_style)]
+        #[derive(Clone)]
+        pub enum #body_ident {
+            #(#variants,)*
+        }
+
+        impl #body_ident {
+            /// The predicate with its value at placeholder `$bind_idx`, e.g. `status = $1`.
+            pub fn predicate(&self, bind_idx: usize) -> String {
+                match self {
+                    #(#predicate_arms,)*
+                }
+            }
+        }
+
+        impl mae::repo::__private__::ToSqlParts for #body_ident {
+            fn to_sql_parts(&self) -> mae::repo::__private__::AsSqlParts {
+                // NOTE: the bind_idx is only known once filters are combined; assume $1 here
+                (vec![self.predicate(1)], None)
+            }
+        }
+
+        impl mae::repo::__private__::BindArgs for #body_ident {
+            fn bind(&self, mut args: &mut sqlx::postgres::PgArguments) {
+                let _ = match self {
+                    #(#bind_arms,)*
+                };
+            }
+            fn bind_len(&self) -> usize {
+                1
+            }
+        }
+
+        #bind_consistency
+
+        impl std::fmt::Debug for #body_ident {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                match self {
+                    #(#debug_arms,)*
+                }
+            }
+        }
+
+        impl #body_ident {
+            /// `(self AND other)`.
+            pub fn and(self, other: impl Into<FilterTree>) -> FilterTree {
+                FilterTree::Leaf(self).and(other)
+            }
+
+            /// `(self OR other)`.
+            pub fn or(self, other: impl Into<FilterTree>) -> FilterTree {
+                FilterTree::Leaf(self).or(other)
+            }
+        }
+
+        /// Nested AND/OR of `Filter`s, e.g. `(a = $1 AND (b > $2 OR c = $3))`.
+        #[derive(Clone, Debug)]
+        pub enum FilterTree {
+            And(Vec<FilterTree>),
+            Or(Vec<FilterTree>),
+            Leaf(#body_ident),
+        }
+
+        impl From<#body_ident> for FilterTree {
+            fn from(filter: #body_ident) -> Self {
+                Self::Leaf(filter)
+            }
+        }
+
+        impl FilterTree {
+            /// `(self AND other)`, flattening into an existing `And`.
+            pub fn and(self, other: impl Into<FilterTree>) -> FilterTree {
+                match self {
+                    Self::And(mut children) => {
+                        children.push(other.into());
+                        Self::And(children)
+                    }
+                    tree => Self::And(vec![tree, other.into()]),
+                }
+            }
+
+            /// `(self OR other)`, flattening into an existing `Or`.
+            pub fn or(self, other: impl Into<FilterTree>) -> FilterTree {
+                match self {
+                    Self::Or(mut children) => {
+                        children.push(other.into());
+                        Self::Or(children)
+                    }
+                    tree => Self::Or(vec![tree, other.into()]),
+                }
+            }
+
+            /// Renders the predicate, numbering placeholders from `*bind_idx` in depth-first
+            /// order (the order `BindArgs::bind` binds in) and leaving `*bind_idx` on the next one.
+            /// Empty groups render as `TRUE` (AND) / `FALSE` (OR).
+            pub fn render(&self, bind_idx: &mut usize) -> String {
+                let (children, sep, empty) = match self {
+                    Self::Leaf(filter) => {
+                        let sql = filter.predicate(*bind_idx);
+                        *bind_idx += 1;
+                        return sql;
+                    }
+                    Self::And(children) => (children, " AND ", "TRUE"),
+                    Self::Or(children) => (children, " OR ", "FALSE"),
+                };
+                if children.is_empty() {
+                    return empty.to_string();
+                }
+                let sql = children.iter().map(|c| c.render(bind_idx)).collect::<Vec<_>>();
+                format!("({})", sql.join(sep))
+            }
+        }
+
+        impl mae::repo::__private__::ToSqlParts for FilterTree {
+            fn to_sql_parts(&self) -> mae::repo::__private__::AsSqlParts {
+                let mut bind_idx = 1;
+                let sql = self.render(&mut bind_idx);
+                (vec![sql], Some((1..bind_idx).map(|i| format!("${}", i)).collect()))
+            }
+        }
+
+        impl mae::repo::__private__::BindArgs for FilterTree {
+            fn bind(&self, args: &mut sqlx::postgres::PgArguments) {
+                match self {
+                    Self::Leaf(filter) => mae::repo::__private__::BindArgs::bind(filter, args),
+                    Self::And(children) | Self::Or(children) => {
+                        children.iter().for_each(|c| mae::repo::__private__::BindArgs::bind(c, args))
+                    }
+                }
+            }
+            fn bind_len(&self) -> usize {
+                match self {
+                    Self::Leaf(filter) => mae::repo::__private__::BindArgs::bind_len(filter),
+                    Self::And(children) | Self::Or(children) => {
+                        children.iter().map(mae::repo::__private__::BindArgs::bind_len).sum()
+                    }
+                }
+            }
+        }
+    };
+    (body, body_ident,)
+}
+
+pub fn to_table(ast: &DeriveInput,) -> Body {
+    let table = match find_struct_attr_with_args(ast, "table",) {
+        Ok(Some(table,),) => table,
+        Ok(None,) => return quote! {},
+        Err(e,) => return e.to_compile_error(),
+    };
+    if table.trim().is_empty() {
+        return ast
+            .attrs
+            .iter()
+            .filter(|a| a.path().is_ident("table",),)
+            .map(|a| {
+                syn::Error::new_spanned(a, "the table name can't be empty",).to_compile_error()
+            },)
+            .collect();
+    }
+
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) => &fields.named,
+        _ => {
+            return syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",)
+                .to_compile_error();
+        }
+    };
+
+    // DDL from the same type mapping as Field::describe(); `id` becomes the serial primary key
+    let columns = fields
+        .iter()
+        .filter_map(|f| {
+            let is_id = f.ident.as_ref()? == "id";
+            let name = column_name(f,);
+            let (sql_type, nullable,) = to_sql_type(f,);
+            let column = match (is_id, sql_type.as_str(),) {
+                (true, "INTEGER",) => format!("{} SERIAL PRIMARY KEY", name),
+                (true, "BIGINT",) => format!("{} BIGSERIAL PRIMARY KEY", name),
+                (true, _,) => format!("{} {} PRIMARY KEY", name, sql_type),
+                (false, _,) if nullable => format!("{} {}", name, sql_type),
+                (false, _,) => format!("{} {} NOT NULL", name, sql_type),
+            };
+            Some(match find_get_attr_with_args(f, "default",) {
+                Ok(Some((_, expr,),),) => format!("{} DEFAULT {}", column, expr),
+                _ => column,
+            },)
+        },)
+        .collect::<Vec<_,>>();
+    let create_table = format!("CREATE TABLE {} ({})", table, columns.join(", ",));
+
+    let (ctx_param, ctx_arg,) = context_param(ast,);
+    // #[from_context] columns come last, numbered after whatever this row binds
+    let ctx_parts = match ctx_arg.is_empty() {
+        true => quote! {},
+        false => quote! {
+            let start = mae::repo::__private__::BindArgs::bind_len(self) + 1;
+            let (ctx_columns, _) = mae::repo::__private__::ToSqlParts::to_sql_parts(#ctx_arg);
+            values.extend((start..start + ctx_columns.len()).map(|i| format!("${}", i)));
+            columns.extend(ctx_columns);
+        },
+    };
+    let repo_ident = &ast.ident;
+    quote! {
+        impl #repo_ident {
+            /// The table this repo reads from and writes to.
+            pub const TABLE: &'static str = #table;
+
+            /// `CREATE TABLE` for this repo, e.g. to set up test schemas without migrations.
+            pub fn sql_create_table() -> String {
+                #create_table.to_string()
+            }
+
+            /// `SELECT <fields> FROM <table>`; `Field::All` (or no fields at all) selects every column.
+            pub fn select_sql(fields: &[Field]) -> String {
+                let columns = match fields.is_empty() {
+                    true => Field::All.to_string(),
+                    false => fields.iter().map(|f| f.to_string()).collect::<Vec<_>>().join(", "),
+                };
+                format!("SELECT {} FROM {}", columns, Self::TABLE)
+            }
+
+            /// `RETURNING <all columns>`, in `Field::All` order, to append to insert/update SQL.
+            pub fn returning_clause() -> String {
+                Self::returning(&[])
+            }
+
+            /// `RETURNING <fields>`; `Field::All` (or no fields at all) returns every column.
+            pub fn returning(fields: &[Field]) -> String {
+                let columns = match fields.is_empty() {
+                    true => Field::All.to_string(),
+                    false => fields.iter().map(|f| f.to_string()).collect::<Vec<_>>().join(", "),
+                };
+                format!("RETURNING {}", columns)
+            }
+
+            /// `SELECT <all columns> FROM <table> WHERE <filter>`; bind the filter's `BindArgs`.
+            pub fn select_by(filter: &Filter) -> String {
+                format!("SELECT {} FROM {} WHERE {}", Field::All, Self::TABLE, filter.predicate(1))
+            }
+        }
+
+        impl InsertRow {
+            /// `INSERT INTO <table> (<columns>) VALUES (<placeholders>)` for exactly the columns this
+            /// row writes, e.g. an unset `#[default]` column renders its SQL expression instead of a
+            /// `$n`. Bind with this row's `BindArgs`, then the `ContextRow`'s when it takes one.
+            pub fn insert_sql(&self #ctx_param) -> String {
+                let (mut columns, values) = mae::repo::__private__::ToSqlParts::to_sql_parts(self);
+                let mut values = values.unwrap_or_default();
+                #ctx_parts
+                format!(
+                    "INSERT INTO {} ({}) VALUES ({})",
+                    #repo_ident::TABLE,
+                    columns.join(", "),
+                    values.join(", ")
+                )
+            }
+        }
+    }
+}
+
+pub fn to_fields(ast: &DeriveInput,) -> (Body, BodyIdent,) {
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) => &fields.named,
+        _ => {
+            return (
+                syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",)
+                    .to_compile_error(),
+                quote! { Field },
+            );
+        }
+    };
+
+    let mut all_cols: Vec<String,> = Vec::new();
+    let mut to_string_arms: Vec<proc_macro2::TokenStream,> = Vec::new();
+    let mut variants: Vec<proc_macro2::TokenStream,> = Vec::new();
+    let mut describe: Vec<proc_macro2::TokenStream,> = Vec::new();
+    let mut non_json_cols: Vec<String,> = Vec::new();
+    let mut writable_cols: Vec<String,> = Vec::new();
+
+    let body_ident = quote! { Field };
+
+    for f in fields.iter() {
+        let Some(name,) = f.ident.as_ref() else {
+            variants.push(
+                syn::Error::new_spanned(f, "expected a named field (missing ident)",)
+                    .to_compile_error(),
+            );
+            continue;
+        };
+
+        let name_str = column_name(f,);
+
+        all_cols.push(name_str.clone(),);
+
+        to_string_arms.push(quote! {
+            #body_ident::#name => #name_str.to_string()
+        },);
+
+        variants.push(quote! { #name },);
+
+        let (sql_type, nullable,) = to_sql_type(f,);
+        if sql_type != "JSONB" {
+            non_json_cols.push(name_str.clone(),);
+        }
+        if ["locked", "insert_only", "update_only", "from_context",]
+            .iter()
+            .all(|a| find_get_attr(f, a,).is_none(),)
+        {
+            writable_cols.push(name_str.clone(),);
+        }
+        let updatable = find_get_attr(f, "locked",).is_none()
+            && find_get_attr(f, "insert_only",).is_none()
+            && find_get_attr(f, "from_context",).is_none();
+        // the type as written, minus the token spacing, e.g. `Option<String>`
+        let ty = &f.ty;
+        let rust_type = quote! { #ty }.to_string().replace(' ', "",);
+        describe.push(quote! {
+            ColumnMeta {
+                field: #body_ident::#name,
+                sql_type: #sql_type,
+                rust_type: #rust_type,
+                nullable: #nullable,
+                updatable: #updatable,
+            }
+        },);
+    }
+
+    let all_cols_str = all_cols.join(", ",);
+    let repo_ident = &ast.ident;
+    let non_json_cols_str = non_json_cols.join(", ",);
+
+    let body = quote! {
+        #[allow(non_snake_case, non_camel_case_types, nonstandard_style)]
+        #[derive(Clone, PartialEq, Eq, Hash)]
+        pub enum #body_ident {
+            All,
+            #(#variants,)*
+        }
+
+        impl #repo_ident {
+            /// Every column name in declaration order, as in `Field::All`.
+            pub const FIELDS: &'static [&'static str] = &[#(#all_cols,)*];
+
+            /// Every column, for building a SELECT list.
+            pub fn selectable_fields() -> Vec<&'static str> {
+                Self::FIELDS.to_vec()
+            }
+
+            /// Columns written on both insert and update, i.e. not `#[locked]`/`#[readonly]`,
+            /// `#[insert_only]`, `#[update_only]` or `#[from_context]`; for building a SET list.
+            pub fn writable_fields() -> Vec<&'static str> {
+                vec![#(#writable_cols,)*]
+            }
+        }
+
+        /// Per-column metadata produced by `Field::describe()`.
+        #[derive(Clone)]
+        pub struct ColumnMeta {
+            pub field: #body_ident,
+            pub sql_type: &'static str,
+            /// The field's type as written in the struct, e.g. `Option<String>`.
+            pub rust_type: &'static str,
+            pub nullable: bool,
+            pub updatable: bool,
+        }
+
+        impl #body_ident {
+            /// Every column variant in declaration order, without `All`.
+            pub fn iter() -> impl Iterator<Item = #body_ident> + 'static {
+                [#(#body_ident::#variants,)*].into_iter()
+            }
+
+            /// Every column with its SQL and Rust types, nullability and whether it can be updated.
+            pub fn describe() -> &'static [ColumnMeta] {
+                const DESCRIBE: &[ColumnMeta] = &[#(#describe,)*];
+                DESCRIBE
+            }
+
+            /// The column(s) qualified with a table alias, e.g. `o.id` (or `o.id, o.status, ...` for `All`).
+            pub fn prefixed(&self, alias: &str) -> String {
+                match self {
+                    Self::All => [#(#all_cols,)*]
+                        .iter()
+                        .map(|c| format!("{}.{}", alias, c))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    _ => format!("{}.{}", alias, self),
+                }
+            }
+
+            /// Every column qualified with a table alias, for use in joined queries.
+            pub fn columns_with_prefix(alias: &str) -> String {
+                Self::All.prefixed(alias)
+            }
+
+            /// `<column> = $bind_idx`, e.g. `email = $2`. Takes no receiver, so `a.eq(&b)` is still
+            /// `PartialEq::eq`.
+            pub fn eq(field: #body_ident, bind_idx: usize) -> String {
+                format!("{} = ${}", field, bind_idx)
+            }
+
+            /// Every column except the json ones (`tags`, `sys_detail`, `#[sqlx(json)]`, `Value`),
+            /// for list queries that don't need the heavy payloads.
+            pub fn columns_except_json() -> String {
+                #non_json_cols_str.to_string()
+            }
+
+            /// Test helper: panics naming every column missing from `touched` (`All` covers all).
+            #[track_caller]
+            pub fn must_cover_all_fields(touched: &[#body_ident]) {
+                if touched.iter().any(|t| matches!(t, Self::All)) {
+                    return;
+                }
+                let missing = Self::iter()
+                    .filter(|f| {
+                        !touched.iter().any(|t| std::mem::discriminant(t) == std::mem::discriminant(f))
+                    })
+                    .map(|f| f.to_string())
+                    .collect::<Vec<_>>();
+                if !missing.is_empty() {
+                    panic!(
+                        "fields not covered: {} at {}",
+                        missing.join(", "),
+                        std::panic::Location::caller()
+                    );
+                }
+            }
+        }
+
+        impl mae::repo::__private__::ToSqlParts for #body_ident {
+            fn to_sql_parts(&self) -> mae::repo::__private__::AsSqlParts {
+                (vec![self.to_string()], None)
+            }
+        }
+
+        /// `WHERE a = $1 AND b = $2 ...` over `Field`s, numbering placeholders in the order the
+        /// conditions were added. Bind the values in that same order.
+        #[derive(Clone, Debug)]
+        pub struct WhereBuilder {
+            start: usize,
+            conditions: Vec<String>,
+        }
+
+        impl Default for WhereBuilder {
+            fn default() -> Self {
+                Self::starting_at(1)
+            }
+        }
+
+        impl WhereBuilder {
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            /// Numbers the first condition `$start`, e.g. after the `$n`s of a SET list.
+            pub fn starting_at(start: usize) -> Self {
+                Self { start, conditions: vec![] }
+            }
+
+            /// Adds `<field> = $<next>`.
+            pub fn eq(mut self, field: #body_ident) -> Self {
+                let bind_idx = self.next_bind_idx();
+                self.conditions.push(#body_ident::eq(field, bind_idx));
+                self
+            }
+
+            /// The placeholder the next condition will use.
+            pub fn next_bind_idx(&self) -> usize {
+                self.start + self.conditions.len()
+            }
+
+            /// The clause, or an empty string when no condition was added.
+            pub fn render(&self) -> String {
+                match self.conditions.is_empty() {
+                    true => String::new(),
+                    false => format!("WHERE {}", self.conditions.join(" AND ")),
+                }
+            }
+        }
+
+        impl std::fmt::Display for #body_ident {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{}", match self {
+                    Self::All => #all_cols_str.into(),
+                    #(#to_string_arms,)*
+                })
+            }
+        }
+    };
+
+    (body, body_ident,)
+}
+
+pub fn to_tenant(ast: &DeriveInput,) -> (Body, BodyIdent,) {
+    let body_ident = quote! { TenantMismatch };
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) => &fields.named,
+        _ => {
+            return (
+                syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",)
+                    .to_compile_error(),
+                body_ident,
+            );
+        }
+    };
+
+    // only tenant-scoped repos (those carrying a sys_client column) get the guard
+    let Some(sys_client,) =
+        fields.iter().find(|f| f.ident.as_ref().is_some_and(|i| i == "sys_client",),)
+    else {
+        return (quote! {}, body_ident,);
+    };
+    let tenant_ty = &sys_client.ty;
+
+    let repo_ident = &ast.ident;
+    let repo_str = repo_ident.to_string();
+
+    let body = quote! {
+        /// Returned when a row is accessed under a tenant (`sys_client`) it does not belong to.
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct #body_ident {
+            pub expected: #tenant_ty,
+            pub actual: #tenant_ty,
+        }
+
+        impl std::fmt::Display for #body_ident {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(
+                    f,
+                    "{} belongs to sys_client {}, expected sys_client {}",
+                    #repo_str, self.actual, self.expected
+                )
+            }
+        }
+
+        impl std::error::Error for #body_ident {}
+
+        impl #repo_ident {
+            /// True when this row belongs to the given tenant.
+            pub fn in_tenant(&self, sys_client: #tenant_ty) -> bool {
+                self.sys_client == sys_client
+            }
+
+            /// Errors with `TenantMismatch` when this row belongs to another tenant.
+            pub fn assert_tenant(&self, sys_client: #tenant_ty) -> Result<(), #body_ident> {
+                if self.sys_client == sys_client {
+                    return Ok(());
+                }
+                Err(#body_ident { expected: sys_client, actual: self.sys_client.clone() })
+            }
+
+            /// Like `assert_tenant`, taking the expected tenant from the request context through
+            /// `ContextColumns::sys_client`.
+            pub fn validate_tenant_scoped<Ctx: ContextColumns + ?Sized>(
+                &self,
+                ctx: &Ctx,
+            ) -> Result<(), #body_ident> {
+                self.assert_tenant(ctx.sys_client())
+            }
+        }
+    };
+    (body, body_ident,)
+}
+
+/// `#[from_context]` columns are left out of the rows and patches; `ContextRow` carries them
+/// instead, filled from the `#[context(Ctx)]` type through the generated `ContextColumns` trait.
+pub fn to_context(ast: &DeriveInput,) -> (Body, BodyIdent,) {
+    let body_ident = quote! { ContextRow };
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) => &fields.named,
+        _ => {
+            return (
+                syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",)
+                    .to_compile_error(),
+                body_ident,
+            );
+        }
+    };
+
+    let context_fields =
+        fields.iter().filter(|f| find_get_attr(f, "from_context",).is_some(),).collect::<Vec<_,>>();
+
+    // what the request context hands over: every #[from_context] column, and the tenant for the
+    // guard `to_tenant` generates on repos with a sys_client column
+    let sys_client = fields.iter().find(|f| f.ident.as_ref().is_some_and(|i| i == "sys_client",),);
+    let accessors = context_fields
+        .iter()
+        .copied()
+        .chain(sys_client.filter(|f| find_get_attr(f, "from_context",).is_none(),),)
+        .filter_map(|f| {
+            let name = f.ident.as_ref()?;
+            let ty = &f.ty;
+            Some(quote! { fn #name(&self) -> #ty; },)
+        },)
+        .collect::<Vec<_,>>();
+    if accessors.is_empty() {
+        return (quote! {}, body_ident,);
+    }
+    let context_columns = quote! {
+        /// Implemented by the request context for the columns it carries: each `#[from_context]`
+        /// column, and `sys_client` for `validate_tenant_scoped`.
+        pub trait ContextColumns {
+            #(#accessors)*
+        }
+    };
+    if context_fields.is_empty() {
+        return (context_columns, body_ident,);
+    }
+
+    let ctx = match ast.attrs.iter().find(|a| a.path().is_ident("context",),) {
+        Some(attr,) => match attr.parse_args::<syn::Path>() {
+            Ok(ctx,) => ctx,
+            Err(_,) => {
+                return (
+                    syn::Error::new_spanned(attr, "expected #[context(Ctx)]",).to_compile_error(),
+                    body_ident,
+                );
+            }
+        },
+        None => {
+            return (
+                syn::Error::new_spanned(
+                    &ast.ident,
+                    "#[from_context] fields need the context type, add #[context(Ctx)]",
+                )
+                .to_compile_error(),
+                body_ident,
+            );
+        }
+    };
+
+    let mut props = vec![];
+    let mut from_ctx = vec![];
+    let mut names = vec![];
+    let mut bind_some = vec![];
+    for f in context_fields {
+        let Some(name_ident,) = f.ident.as_ref() else {
+            continue;
+        };
+        let ty = &f.ty;
+        let name_str = column_name(f,);
+
+        props.push(quote! { pub #name_ident: #ty },);
+        from_ctx.push(quote! { #name_ident: ContextColumns::#name_ident(ctx) },);
+        names.push(quote! { #name_str.to_string() },);
+        bind_some.push(quote! {
+            let _ = args.add(&self.#name_ident);
+        },);
+    }
+    let count = props.len();
+
+    let body = quote! {
+        #context_columns
+
+        /// The `#[from_context]` columns, bound after an `InsertRow`'s own.
+        #[allow(non_snake_case, non_camel_case_types, nonstandard_style)]
+        #[derive(Debug, Clone)]
+        pub struct #body_ident {
+            #(#props,)*
+        }
+
+        impl #body_ident {
+            /// Reads each `#[from_context]` column off the request context.
+            pub fn from_context(ctx: &#ctx) -> Self {
+                Self {
+                    #(#from_ctx,)*
+                }
+            }
+        }
+
+        impl mae::repo::__private__::ToSqlParts for #body_ident {
+            fn to_sql_parts(&self) -> mae::repo::__private__::AsSqlParts {
+                // NOTE: placeholders are left to the caller, which knows the row's offset
+                (vec![#(#names,)*], None)
+            }
+        }
+
+        impl mae::repo::__private__::BindArgs for #body_ident {
+            fn bind(&self, mut args: &mut sqlx::postgres::PgArguments) {
+                #(#bind_some)*
+            }
+            fn bind_len(&self) -> usize {
+                #count
+            }
+        }
+    };
+    (body, body_ident,)
+}
+
+pub fn to_json_accessors(ast: &DeriveInput,) -> Body {
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) => &fields.named,
+        _ => {
+            return syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",)
+                .to_compile_error();
+        }
+    };
+
+    // one accessor per default json column (`tags`, `sys_detail`) present on the repo
+    let accessors = fields
+        .iter()
+        .filter_map(|f| f.ident.as_ref(),)
+        .filter(|i| *i == "tags" || *i == "sys_detail",)
+        .map(|name| {
+            let getter = syn::Ident::new(&format!("{}_get", name), name.span(),);
+            let doc = format!("Looks up a JSON pointer (e.g. `/meta/source`) in `{}`.", name);
+            quote! {
+                #[doc = #doc]
+                pub fn #getter(&self, pointer: &str) -> Option<&serde_json::Value> {
+                    self.#name.pointer(pointer)
+                }
+            }
+        },)
+        .collect::<Vec<_,>>();
+
+    if accessors.is_empty() {
+        return quote! {};
+    }
+
+    let repo_ident = &ast.ident;
+    quote! {
+        impl #repo_ident {
+            #(#accessors)*
+        }
+    }
+}
+
+/// `#[enum_column(variants("A", "B"))]` on a field emits `Repo::<FIELD>_VARIANTS`, since a derive
+/// can't see the variants of the field's enum itself.
+pub fn to_enum_columns(ast: &DeriveInput,) -> Body {
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) => &fields.named,
+        _ => {
+            return syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",)
+                .to_compile_error();
+        }
+    };
+
+    let mut consts = vec![];
+    for f in fields.iter() {
+        let attr = f.attrs.iter().find(|a| a.path().is_ident("enum_column",),);
+        let (Some(name,), Some(attr,),) = (f.ident.as_ref(), attr,) else {
+            continue;
+        };
+        let variants = attr.parse_args_with(|input: syn::parse::ParseStream<'_,>| {
+            let kw: syn::Ident = input.parse()?;
+            if kw != "variants" {
+                return Err(syn::Error::new_spanned(&kw, "expected `variants(...)`",),);
+            }
+            let content;
+            syn::parenthesized!(content in input);
+            syn::punctuated::Punctuated::<LitStr, syn::Token![,],>::parse_terminated(&content,)
+        },);
+        let variants = match variants {
+            Ok(v,) if !v.is_empty() => v.into_iter().collect::<Vec<_,>>(),
+            _ => {
+                consts.push(
+                    syn::Error::new_spanned(
+                        attr,
+                        "expected #[enum_column(variants(\"A\", \"B\"))] with at least one variant",
+                    )
+                    .to_compile_error(),
+                );
+                continue;
+            }
+        };
+
+        let const_ident =
+            syn::Ident::new(&format!("{}_VARIANTS", name.to_string().to_uppercase()), name.span(),);
+        let doc = format!("The variant names `{}` takes, as given to `#[enum_column]`.", name);
+        consts.push(quote! {
+            #[doc = #doc]
+            pub const #const_ident: &'static [&'static str] = &[#(#variants,)*];
+        },);
+    }
+
+    if consts.is_empty() {
+        return quote! {};
+    }
+
+    let repo_ident = &ast.ident;
+    quote! {
+        impl #repo_ident {
+            #(#consts)*
+        }
+    }
+}
+
+pub fn to_status(ast: &DeriveInput,) -> Body {
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) => &fields.named,
+        _ => {
+            return syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",)
+                .to_compile_error();
+        }
+    };
+
+    let Some(status,) = fields.iter().find(|f| f.ident.as_ref().is_some_and(|i| i == "status",),)
+    else {
+        return quote! {};
+    };
+
+    let repo_ident = &ast.ident;
+    let ty = &status.ty;
+    quote! {
+        impl #repo_ident {
+            /// True when this row's `status` is `s`.
+            pub fn status_is(&self, s: #ty) -> bool {
+                self.status == s
+            }
+
+            /// `status = $1` with `s` bound as `$1`.
+            pub fn status_predicate(s: #ty) -> (String, sqlx::postgres::PgArguments) {
+                let mut args = sqlx::postgres::PgArguments::default();
+                let _ = sqlx::Arguments::add(&mut args, s);
+                ("status = $1".to_string(), args)
+            }
+        }
+    }
+}
+
+pub fn to_reset_audit(ast: &DeriveInput,) -> Body {
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) => &fields.named,
+        _ => {
+            return syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",)
+                .to_compile_error();
+        }
+    };
+
+    let locked = fields.iter().filter_map(|f| find_get_attr(f, "locked",).map(|i| (i, f,),),);
+    let mut names = vec![];
+    let mut resets = vec![];
+    for (name, f,) in locked {
+        // timestamps restart at now(), everything else (ids, audit user ids) at its Default
+        resets.push(match type_name(&f.ty,).as_deref() {
+            Some("DateTime",) => quote! { self.#name = chrono::Utc::now().into(); },
+            _ => quote! { self.#name = Default::default(); },
+        },);
+        names.push(format!("`{}`", name),);
+    }
+    if resets.is_empty() {
+        return quote! {};
+    }
+
+    let repo_ident = &ast.ident;
+    let doc = format!(
+        "Resets the `#[locked]` columns ({}) so the row can be inserted as a new record: \
+         timestamps become now, everything else its `Default` (`0` for ids).",
+        names.join(", ",)
+    );
+    quote! {
+        impl #repo_ident {
+            #[doc = #doc]
+            pub fn reset_audit(&mut self) {
+                #(#resets)*
+            }
+        }
+    }
+}
+
+/// `#[validate(with = "path::to::check")]` on the repo struct routes `InsertRow::validate()`
+/// through `check(&InsertRow) -> Result<(), ValidationError>`.
+pub fn to_validate(ast: &DeriveInput,) -> (Body, BodyIdent,) {
+    let body_ident = quote! { ValidationError };
+    let Some(attr,) = ast.attrs.iter().find(|a| a.path().is_ident("validate",),) else {
+        return (quote! {}, body_ident,);
+    };
+
+    let validator = attr
+        .parse_args::<syn::MetaNameValue>()
+        .ok()
+        .filter(|nv| nv.path.is_ident("with",),)
+        .and_then(|nv| match nv.value {
+            syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit,), .. },) => Some(lit,),
+            _ => None,
+        },)
+        .and_then(|lit| lit.parse::<syn::Path>().ok(),);
+    let Some(validator,) = validator else {
+        return (
+            syn::Error::new_spanned(attr, "expected #[validate(with = \"path::to::check\")]",)
+                .to_compile_error(),
+            body_ident,
+        );
+    };
+
+    let body = quote! {
+        /// Returned by `InsertRow::validate()` when a business rule rejects the row.
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct #body_ident {
+            pub message: String,
+        }
+
+        impl std::fmt::Display for #body_ident {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{}", self.message)
+            }
+        }
+
+        impl std::error::Error for #body_ident {}
+
+        impl InsertRow {
+            /// Runs the repo's `#[validate]` rules; call before inserting.
+            pub fn validate(&self) -> Result<(), #body_ident> {
+                #validator(self)
+            }
+        }
+    };
+    (body, body_ident,)
+}
+
+pub fn to_pk(ast: &DeriveInput,) -> Body {
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) => &fields.named,
+        _ => {
+            return syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",)
+                .to_compile_error();
+        }
+    };
+
+    let Some(id,) = fields.iter().find(|f| f.ident.as_ref().is_some_and(|i| i == "id",),) else {
+        return quote! {};
+    };
+
+    let repo_ident = &ast.ident;
+    let id_ty = &id.ty;
+    let in_clause = format!("{} IN ({{}})", column_name(id,));
+    quote! {
+        impl #repo_ident {
+            /// `id IN ($start, $start + 1, ...)` with one placeholder per id (`FALSE` when empty).
+            /// Bind the ids with `bind_pk_in`.
+            pub fn pk_in_clause(ids: &[#id_ty], start: usize) -> String {
+                if ids.is_empty() {
+                    return "FALSE".to_string();
+                }
+                let placeholders = (start..start + ids.len())
+                    .map(|i| format!("${}", i))
+                    .collect::<Vec<_>>();
+                format!(#in_clause, placeholders.join(", "))
+            }
+
+            /// Binds `ids` in order, matching the placeholders of `pk_in_clause`.
+            pub fn bind_pk_in(ids: &[#id_ty], args: &mut sqlx::postgres::PgArguments) {
+                for id in ids {
+                    let _ = sqlx::Arguments::add(args, id);
+                }
+            }
+        }
+    }
+}
+
+pub fn to_touch(ast: &DeriveInput,) -> (Body, BodyIdent,) {
+    let body_ident = quote! { PatchField };
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) => &fields.named,
+        _ => {
+            return (
+                syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",)
+                    .to_compile_error(),
+                body_ident,
+            );
+        }
+    };
+
+    // the audit columns are only present on repos built with #[schema] (or mirroring it)
+    let find = |name: &str| fields.iter().find(|f| f.ident.as_ref().is_some_and(|i| i == name,),);
+    let (Some(id,), Some(updated_at,), Some(updated_by,),) =
+        (find("id",), find("updated_at",), find("updated_by",),)
+    else {
+        return (quote! {}, body_ident,);
+    };
+    // the UPDATE targets Self::TABLE, which only exists with a table
+    match find_struct_attr_with_args(ast, "table",) {
+        Ok(Some(_,),) => {}
+        Ok(None,) => return (quote! {}, body_ident,),
+        Err(e,) => return (e.to_compile_error(), body_ident,),
+    }
 
-            }
-        }
+    let repo_ident = &ast.ident;
+    let id_ty = &id.ty;
+    let updated_by_ty = &updated_by.ty;
+    let touch =
+        format!("{} = now(), {} = ${{}}", column_name(updated_at,), column_name(updated_by,),);
+    let where_id = format!("{} = ${{}}", column_name(id,));
 
-        impl mae::repo::__private__::BindArgs for #body_ident {
-            fn bind(&self, mut args: &mut sqlx::postgres::PgArguments) {
-                let _ = match self {
-                    #(#to_arg,)*
+    let body = quote! {
+        impl #repo_ident {
+            /// Builds `UPDATE <table> SET <patches>, updated_at = now(), updated_by = $n WHERE id = $n`
+            /// with the arguments bound in placeholder order: patches, `updated_by`, then `id`.
+            pub fn touch_and_patch(
+                patches: Vec<#body_ident>,
+                updated_by: #updated_by_ty,
+                id: #id_ty,
+            ) -> (String, sqlx::postgres::PgArguments) {
+                let patches = PatchSet(patches);
+                let (set, next) = patches.set_clause(1);
+                let touch = format!(#touch, next);
+                let set = match set.is_empty() {
+                    true => format!("SET {}", touch),
+                    false => format!("{}, {}", set, touch),
                 };
-            }
-            fn bind_len(&self) -> usize {
-                // NOTE: There will always be one arg for a PatchField
-                1
-            }
-        }
 
-        impl std::fmt::Debug for #body_ident {
-            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-                match self {
-                    #(#debug_bindings,)*
-                }
+                let mut args = sqlx::postgres::PgArguments::default();
+                mae::repo::__private__::BindArgs::bind(&patches, &mut args);
+                let _ = sqlx::Arguments::add(&mut args, updated_by);
+                let _ = sqlx::Arguments::add(&mut args, id);
+
+                let where_id = format!(#where_id, next + 1);
+                (format!("UPDATE {} {} WHERE {}", Self::TABLE, set, where_id), args)
             }
         }
     };
     (body, body_ident,)
 }
 
-pub fn to_fields(ast: &DeriveInput,) -> (Body, BodyIdent,) {
+pub fn to_array_checks(ast: &DeriveInput,) -> Body {
     let fields = match &ast.data {
         Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) => &fields.named,
         _ => {
-            return (
-                syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",)
-                    .to_compile_error(),
-                quote! { Field },
-            );
+            return syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",)
+                .to_compile_error();
         }
     };
 
-    let mut all_cols: Vec<String,> = Vec::new();
-    let mut to_string_arms: Vec<proc_macro2::TokenStream,> = Vec::new();
-    let mut variants: Vec<proc_macro2::TokenStream,> = Vec::new();
+    // Vec<T> of a custom type only binds when T: PgHasArrayType; without this check the missing
+    // impl surfaces deep inside sqlx's Encode/Type bounds instead of on the offending field.
+    // `#[sqlx(json)]` columns are stored as JSONB and never bound as arrays.
+    let checks = fields
+        .iter()
+        .filter(|f| to_sql_type(f,).0 != "JSONB",)
+        .filter_map(|f| {
+            let ty = option_inner(&f.ty,).unwrap_or(&f.ty,);
+            let inner = generic_inner(ty, "Vec",)?;
+            let name = type_name(inner,)?;
+            if name == "u8" || builtin_sql_type(&name,).is_some() {
+                return None;
+            }
+            Some(quote::quote_spanned! { inner.span() =>
+                __mae_pg_array_column::<#inner>();
+            },)
+        },)
+        .collect::<Vec<_,>>();
 
-    let body_ident = quote! { Field };
+    if checks.is_empty() {
+        return quote! {};
+    }
 
-    for f in fields.iter() {
-        let Some(name,) = f.ident.as_ref() else {
-            variants.push(
-                syn::Error::new_spanned(f, "expected a named field (missing ident)",)
-                    .to_compile_error(),
-            );
-            continue;
-        };
+    quote! {
+        const _: () = {
+            #[diagnostic::on_unimplemented(
+                message = "`{Self}` is used as a `Vec<{Self}>` column but does not implement `sqlx::postgres::PgHasArrayType`",
+                label = "array-of-enum columns need `PgHasArrayType`",
+                note = "derive `sqlx::Type` with `#[sqlx(type_name = \"...\")]` or implement `PgHasArrayType` by hand"
+            )]
+            trait MaeArrayColumn {}
+            impl<T: sqlx::postgres::PgHasArrayType> MaeArrayColumn for T {}
 
-        let name_str = name.to_string();
+            fn __mae_pg_array_column<T: MaeArrayColumn>() {}
 
-        all_cols.push(name_str.clone(),);
+            #[allow(dead_code)]
+            fn __mae_pg_array_columns() {
+                #(#checks)*
+            }
+        };
+    }
+}
 
-        to_string_arms.push(quote! {
-            #body_ident::#name => #name_str.to_string()
-        },);
+pub fn to_conflict(ast: &DeriveInput,) -> Body {
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) => &fields.named,
+        _ => {
+            return syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",)
+                .to_compile_error();
+        }
+    };
 
-        variants.push(quote! { #name },);
+    let unique = fields
+        .iter()
+        .filter(|f| find_get_attr(f, "unique",).is_some(),)
+        .map(column_name,)
+        .collect::<Vec<_,>>();
+    if unique.is_empty() {
+        return quote! {};
     }
 
-    let all_cols_str = all_cols.join(", ",);
+    // the DO NOTHING variant extends insert_sql, which only exists with a table
+    match find_struct_attr_with_args(ast, "table",) {
+        Ok(Some(_,),) => {}
+        Ok(None,) => {
+            return syn::Error::new_spanned(
+                &ast.ident,
+                "#[unique] needs the table name; add #[table(\"...\")] (or use #[schema])",
+            )
+            .to_compile_error();
+        }
+        Err(e,) => return e.to_compile_error(),
+    }
 
-    let body = quote! {
-        #[allow(non_snake_case, non_camel_case_types, nonstandard_style)]
-        #[derive(Clone)]
-        pub enum #body_ident {
-            All,
-            #(#variants,)*
+    let (ctx_param, ctx_arg,) = context_param(ast,);
+    let conflict_ignore = format!("ON CONFLICT ({}) DO NOTHING", unique.join(", ",));
+    let insert_only = fields
+        .iter()
+        .filter(|f| find_get_attr(f, "insert_only",).is_some(),)
+        .map(column_name,)
+        .collect::<Vec<_,>>();
+
+    // several #[unique] fields form one composite target; the rest of the row is overwritten,
+    // except #[insert_only] columns, which keep the value the row was first inserted with
+    let updates = insert_columns(fields,)
+        .into_iter()
+        .filter(|c| !unique.contains(c,) && !insert_only.contains(c,),)
+        .map(|c| format!("{0} = EXCLUDED.{0}", c),)
+        .collect::<Vec<_,>>();
+    let upsert_clause = match updates.is_empty() {
+        true => format!("ON CONFLICT ({}) DO NOTHING", unique.join(", ",)),
+        false => {
+            format!("ON CONFLICT ({}) DO UPDATE SET {}", unique.join(", ",), updates.join(", ",))
         }
+    };
 
-        impl mae::repo::__private__::ToSqlParts for #body_ident {
-            fn to_sql_parts(&self) -> mae::repo::__private__::AsSqlParts {
-                (vec![self.to_string()], None)
+    quote! {
+        impl InsertRow {
+            /// This row's `insert_sql` plus `ON CONFLICT (<#[unique] columns>) DO NOTHING`, so its
+            /// placeholders match what this row's `BindArgs` binds, unset `#[default]`s included.
+            pub fn insert_conflict_ignore_sql(&self #ctx_param) -> String {
+                format!("{} {}", self.insert_sql(#ctx_arg), #conflict_ignore)
             }
-        }
 
-        impl std::fmt::Display for #body_ident {
-            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-                write!(f, "{}", match self {
-                    Self::All => #all_cols_str.into(),
-                    #(#to_string_arms,)*
-                })
+            /// `ON CONFLICT (<#[unique] columns>) DO UPDATE SET ...` to append to this row's
+            /// `INSERT`, overwriting every other inserted column but the `#[insert_only]` ones.
+            pub fn upsert_clause() -> String {
+                #upsert_clause.to_string()
             }
         }
+    }
+}
+
+/// `#[arbitrary]` on the repo struct (`#[test]` is reserved by rustc for functions) generates a
+/// test-only random-row constructor drawing from a caller-supplied `rand::Rng`.
+pub fn to_arbitrary(ast: &DeriveInput,) -> Body {
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) => &fields.named,
+        _ => {
+            return syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",)
+                .to_compile_error();
+        }
     };
 
-    (body, body_ident,)
+    if !ast.attrs.iter().any(|a| a.path().is_ident("arbitrary",),) {
+        return quote! {};
+    }
+
+    let values = fields.iter().filter_map(|f| {
+        let name_ident = f.ident.as_ref()?;
+        let value = match find_get_attr(f, "skip",) {
+            Some(_,) => quote! { Default::default() },
+            None => arbitrary_value(&f.ty, &name_ident.to_string(),),
+        };
+        Some(quote! { #name_ident: #value },)
+    },);
+
+    let repo_ident = &ast.ident;
+    quote! {
+        #[cfg(test)]
+        impl #repo_ident {
+            /// A row of random values drawn from `rng`, e.g. the one `#[mae_test(seed = N)]` binds,
+            /// so a failing row can be replayed. Types without a known generator use
+            /// `Default::default()`.
+            pub fn arbitrary(rng: &mut impl rand::Rng) -> Self {
+                Self {
+                    #(#values,)*
+                }
+            }
+        }
+    }
+}
+
+/// Expression producing a random value of `ty` for `to_arbitrary`.
+fn arbitrary_value(ty: &syn::Type, name: &str,) -> Body {
+    let next = quote! { rand::RngCore::next_u64(&mut *rng) };
+
+    if let Some(inner,) = option_inner(ty,) {
+        let inner = arbitrary_value(inner, name,);
+        return quote! {
+            if #next & 1 == 0 { None } else { Some(#inner) }
+        };
+    }
+
+    match type_name(ty,).as_deref() {
+        Some("bool",) => quote! { #next & 1 == 1 },
+        // kept non-negative so generated ids and foreign keys look plausible
+        Some("i16" | "i32" | "i64",) => quote! { (#next % <#ty>::MAX as u64) as #ty },
+        Some("u8" | "u16" | "u32" | "u64" | "usize",) => quote! { #next as #ty },
+        Some("f32" | "f64",) => quote! { (#next >> 11) as #ty / (1u64 << 53) as #ty },
+        Some("String",) => quote! { format!("{}_{:x}", #name, #next) },
+        Some("Value",) => quote! { serde_json::Value::from(#next) },
+        Some("DateTime",) => quote! {
+            // somewhere between 1970 and 2100
+            chrono::DateTime::<chrono::Utc>::from_timestamp((#next % 4_102_444_800) as i64, 0)
+                .unwrap_or_default()
+        },
+        _ => quote! { Default::default() },
+    }
 }
 
 pub fn to_row(ast: &DeriveInput, attr_black_list: Vec<String,>,) -> (Body, BodyIdent,) {
@@ -187,7 +1548,6 @@ pub fn to_row(ast: &DeriveInput, attr_black_list: Vec<String,>,) -> (Body, BodyI
     };
 
     let is_insert_row = attr_black_list.contains(&"update_only".to_string(),);
-    let is_update_row = !is_insert_row;
 
     let body_ident = if is_insert_row {
         quote! { InsertRow}
@@ -195,11 +1555,36 @@ pub fn to_row(ast: &DeriveInput, attr_black_list: Vec<String,>,) -> (Body, BodyI
         quote! {UpdateRow}
     };
 
+    // extra derives requested with #[row_derive(...)], appended to both rows; PartialEq is always
+    // derived, so asking for it again is a no-op rather than a conflicting impl
+    let mut row_derives = vec![];
+    for attr in ast.attrs.iter().filter(|a| a.path().is_ident("row_derive",),) {
+        match attr.parse_args_with(
+            syn::punctuated::Punctuated::<syn::Path, syn::Token![,],>::parse_terminated,
+        ) {
+            Ok(paths,) => {
+                row_derives.extend(paths.into_iter().filter(|p| !p.is_ident("PartialEq",),),)
+            }
+            Err(_,) => {
+                return (
+                    syn::Error::new_spanned(attr, "expected #[row_derive(Trait, ...)]",)
+                        .to_compile_error(),
+                    body_ident,
+                );
+            }
+        }
+    }
+
     let mut props = vec![];
     let mut string_some = vec![];
     let mut bind_some = vec![];
     let mut bind_len = vec![];
     let mut debug_bindings = vec![];
+    let mut builder_props = vec![];
+    let mut builder_setters = vec![];
+    let mut builder_fields = vec![];
+    let mut full_values = vec![];
+    let mut all_default = true;
 
     fields.iter().for_each(|f| {
         let name_ident = f.ident.as_ref().ok_or_else(|| {
@@ -209,18 +1594,79 @@ pub fn to_row(ast: &DeriveInput, attr_black_list: Vec<String,>,) -> (Body, BodyI
 
         // we need to check if either there are no attrs, or if attr != locked | != insert_only
         if let Ok(name_ident,) = name_ident
-            && f.attrs
-                .iter()
-                .map(|a| {
-                    attr_black_list.iter().map(|abl| !a.path().is_ident(abl,),).all(|a| a == true,)
-                },)
-                .all(|a| a == true,)
+            && f.attrs.iter().all(|a| attr_black_list.iter().all(|abl| !a.path().is_ident(abl,),),)
         {
             let ty = &f.ty;
+            all_default &= known_default(ty,);
+            let sql_default = if is_insert_row {
+                find_get_attr_with_args(f, "default",).ok().flatten().map(|(_, expr,)| expr,)
+            } else {
+                None
+            };
+
             if is_insert_row {
+                builder_props.push(quote! { #name_ident: Option<#ty> },);
+                builder_setters.push(quote! {
+                    pub fn #name_ident(mut self, v: #ty) -> Self {
+                        self.#name_ident = Some(v);
+                        self
+                    }
+                },);
+                // #[default] columns may stay unset, everything else has to be given
+                let missing = format!("missing `{}` in InsertRow", name_ident);
+                builder_fields.push(if sql_default.is_some() {
+                    quote! { #name_ident: self.#name_ident }
+                } else if find_get_attr(f, "nullable",).is_some() {
+                    // an unset #[nullable] column is inserted as NULL
+                    quote! { #name_ident: self.#name_ident.unwrap_or_default() }
+                } else {
+                    quote! { #name_ident: self.#name_ident.ok_or(#missing)? }
+                },);
+            }
+
+            // an unset #[default("expr")] column is written as its SQL expression, unbound. The
+            // blacklist has already picked the row, so with #[insert_only] the default applies on
+            // insert and the column never reaches UpdateRow; #[locked] leaves it to the DDL alone
+            if let Some(expr,) = sql_default {
+                props.push(quote! { pub #name_ident: Option<#ty> },);
+                full_values.push(quote! { #name_ident: Some(Default::default()) },);
+
+                let name_str = column_name(f,);
+                string_some.push(quote! {
+                    sql.push(format!("{}", #name_str));
+                    match &self.#name_ident {
+                        Some(_) => {
+                            i += 1;
+                            sql_i.push(format!("${}", i));
+                        }
+                        None => sql_i.push(#expr.to_string()),
+                    };
+                },);
+
+                bind_len.push(quote! {
+                    if let Some(v) = &self.#name_ident {
+                        count += 1;
+                    };
+                },);
+                bind_some.push(quote! {
+                if let Some(v) = &self.#name_ident {
+                    let _ = args.add(v);
+                };},);
+                debug_bindings.push(quote! {
+                    match &self.#name_ident {
+                        Some(v) => {
+                            sql_i += 1;
+                            write!(f, "\n\t${} ({}) = {:?}", sql_i, #name_str, v)?;
+                        }
+                        None => write!(f, "\n\t{} = {}", #name_str, #expr)?,
+                    };
+                },);
+            // insert columns are all required; on update only the #[update_only] ones are
+            } else if is_insert_row || find_get_attr(f, "update_only",).is_some() {
                 props.push(quote! { pub #name_ident: #ty },);
+                full_values.push(quote! { #name_ident: Default::default() },);
 
-                let name_str = name_ident.to_string();
+                let name_str = column_name(f,);
                 string_some.push(quote! {
                     i += 1;
                     sql.push(format!("{}", #name_str));
@@ -233,14 +1679,25 @@ pub fn to_row(ast: &DeriveInput, attr_black_list: Vec<String,>,) -> (Body, BodyI
                 bind_some.push(quote! {
                     let _ = args.add(&self.#name_ident);
                 },);
-                debug_bindings.push(quote! {
-                    sql_i += 1;
-                    write!(f, "\n\t${} = {:?}", sql_i, &self.#name_ident)?;
+                debug_bindings.push(if find_get_attr(f, "nullable",).is_some() {
+                    quote! {
+                        sql_i += 1;
+                        match &self.#name_ident {
+                            Some(v) => write!(f, "\n\t${} ({}) = {:?}", sql_i, #name_str, v)?,
+                            None => write!(f, "\n\t${} ({}) = NULL", sql_i, #name_str)?,
+                        };
+                    }
+                } else {
+                    quote! {
+                        sql_i += 1;
+                        write!(f, "\n\t${} ({}) = {:?}", sql_i, #name_str, &self.#name_ident)?;
+                    }
                 },)
             } else {
                 props.push(quote! { pub #name_ident: Option<#ty> },);
+                full_values.push(quote! { #name_ident: Some(Default::default()) },);
 
-                let name_str = name_ident.to_string();
+                let name_str = column_name(f,);
                 string_some.push(quote! {
                 if let Some(v) = &self.#name_ident {
                     i += 1;
@@ -260,16 +1717,69 @@ pub fn to_row(ast: &DeriveInput, attr_black_list: Vec<String,>,) -> (Body, BodyI
                 debug_bindings.push(quote! {
                     if let Some(v) = &self.#name_ident {
                         sql_i += 1;
-                        write!(f, "\n\t${} = {:?}", sql_i, v)?;
+                        write!(f, "\n\t${} ({}) = {:?}", sql_i, #name_str, v)?;
                     };
                 },);
             }
         }
     },);
 
+    let bind_consistency = to_bind_consistency(&body_ident,);
+
+    // every Option set, so no column is skipped and every placeholder has to be bound; only in
+    // test builds, and only when every column type is known to be `Default` so that a custom
+    // column type without it can't break the user's `cargo test`
+    let full_consistency = match all_default {
+        true => quote! {
+            impl #body_ident {
+                #[cfg(test)]
+                #[doc(hidden)]
+                pub fn __assert_bind_consistency() {
+                    let full = #body_ident {
+                        #(#full_values,)*
+                    };
+                    full.assert_bind_consistency();
+                }
+            }
+        },
+        false => quote! {},
+    };
+
+    let builder = if is_insert_row {
+        quote! {
+            /// Named setters for `InsertRow`, so adding a column doesn't break every literal.
+            #[allow(non_snake_case, non_camel_case_types, nonstandard_style)]
+            #[derive(Clone, Default)]
+            pub struct InsertRowBuilder {
+                #(#builder_props,)*
+            }
+
+            impl InsertRowBuilder {
+                #(#builder_setters)*
+
+                /// Errors naming the first required column that was never set.
+                pub fn build(self) -> Result<InsertRow, &'static str> {
+                    Ok(InsertRow {
+                        #(#builder_fields,)*
+                    })
+                }
+            }
+
+            impl InsertRow {
+                pub fn builder() -> InsertRowBuilder {
+                    InsertRowBuilder::default()
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
     let body = quote! {
+        #builder
+
         #[allow(non_snake_case, non_camel_case_types, nonstandard_style)]
-        #[derive(Clone)]
+        #[derive(Clone, PartialEq, #(#row_derives),*)]
         pub struct #body_ident {
             #(#props,)*
         }
@@ -296,6 +1806,10 @@ pub fn to_row(ast: &DeriveInput, attr_black_list: Vec<String,>,) -> (Body, BodyI
             }
         }
 
+        #bind_consistency
+
+        #full_consistency
+
         impl std::fmt::Debug for #body_ident {
             fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
                 let mut sql_i = 0;
@@ -307,6 +1821,301 @@ pub fn to_row(ast: &DeriveInput, attr_black_list: Vec<String,>,) -> (Body, BodyI
     (body, body_ident,)
 }
 
+/// Debug helpers comparing the placeholders a type renders in `to_sql_parts` with what its
+/// `BindArgs` binds. Without explicit placeholders every part stands for one value bound later.
+fn to_bind_consistency(body_ident: &BodyIdent,) -> Body {
+    quote! {
+        impl #body_ident {
+            /// True when `bind_len()` equals `placeholder_count`.
+            pub fn bind_count_matches(&self, placeholder_count: usize) -> bool {
+                mae::repo::__private__::BindArgs::bind_len(self) == placeholder_count
+            }
+
+            /// Panics when the `$n` placeholders from `to_sql_parts` don't match `bind_len()`.
+            #[track_caller]
+            pub fn assert_bind_consistency(&self) {
+                let (parts, placeholders) = mae::repo::__private__::ToSqlParts::to_sql_parts(self);
+                let placeholder_count = match placeholders {
+                    Some(placeholders) => placeholders.iter().map(|p| p.matches('$').count()).sum(),
+                    None => parts.len(),
+                };
+                if !self.bind_count_matches(placeholder_count) {
+                    panic!(
+                        "{} renders {} placeholder(s) but binds {} value(s) at {}",
+                        stringify!(#body_ident),
+                        placeholder_count,
+                        mae::repo::__private__::BindArgs::bind_len(self),
+                        std::panic::Location::caller()
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Maps a field's Rust type onto a Postgres column type, returning the type name and whether the
+/// column is nullable (`Option<T>`). Fields marked `#[sqlx(json)]` are always `JSONB`.
+fn to_sql_type(field: &Field,) -> (String, bool,) {
+    let is_json = field.attrs.iter().any(|a| {
+        a.path().is_ident("sqlx",)
+            && a.parse_args::<syn::Ident>().map(|i| i == "json",).unwrap_or(false,)
+    },);
+
+    let (ty, nullable,) = match option_inner(&field.ty,) {
+        Some(inner,) => (inner, true,),
+        None => (&field.ty, false,),
+    };
+
+    if is_json {
+        return ("JSONB".into(), nullable,);
+    }
+
+    (rust_to_sql_type(ty,), nullable,)
+}
+
+fn rust_to_sql_type(ty: &syn::Type,) -> String {
+    if let Some(inner,) = generic_inner(ty, "Vec",) {
+        return match type_name(inner,).as_deref() {
+            Some("u8",) => "BYTEA".into(),
+            _ => format!("{}[]", rust_to_sql_type(inner,)),
+        };
+    }
+
+    match type_name(ty,) {
+        Some(name,) => match builtin_sql_type(&name,) {
+            Some(sql_type,) => sql_type.into(),
+            // custom types (e.g. sqlx::Type enums) keep their Rust name as the SQL type name
+            None => name,
+        },
+        None => "TEXT".into(),
+    }
+}
+
+/// Postgres type for the Rust types sqlx maps out of the box, keyed by the type's last path segment.
+fn builtin_sql_type(name: &str,) -> Option<&'static str,> {
+    Some(match name {
+        "bool" => "BOOLEAN",
+        "i16" => "SMALLINT",
+        "i32" => "INTEGER",
+        "i64" => "BIGINT",
+        "f32" => "REAL",
+        "f64" => "DOUBLE PRECISION",
+        "String" => "TEXT",
+        "Value" => "JSONB",
+        "Uuid" => "UUID",
+        "DateTime" => "TIMESTAMPTZ",
+        "NaiveDateTime" => "TIMESTAMP",
+        "NaiveDate" => "DATE",
+        "NaiveTime" => "TIME",
+        "Decimal" => "NUMERIC",
+        _ => return None,
+    },)
+}
+
+/// Whether `ty` implements `Default` as far as can be told from its name: `Option<_>`, `Vec<_>`
+/// and the builtin column types.
+fn known_default(ty: &syn::Type,) -> bool {
+    generic_inner(ty, "Option",).is_some()
+        || generic_inner(ty, "Vec",).is_some()
+        || type_name(ty,).is_some_and(|n| builtin_sql_type(&n,).is_some(),)
+}
+
+/// The last path segment of `ty`, e.g. `DateTime` for `chrono::DateTime<Utc>`.
+fn type_name(ty: &syn::Type,) -> Option<String,> {
+    match ty {
+        syn::Type::Path(p,) => p.path.segments.last().map(|s| s.ident.to_string(),),
+        _ => None,
+    }
+}
+
+/// Returns `T` when `ty` is `Option<T>`.
+fn option_inner(ty: &syn::Type,) -> Option<&syn::Type,> {
+    generic_inner(ty, "Option",)
+}
+
+/// Returns `T` when `ty` is `<wrapper><T>`, e.g. `Vec<T>`.
+fn generic_inner<'a,>(ty: &'a syn::Type, wrapper: &str,) -> Option<&'a syn::Type,> {
+    let syn::Type::Path(p,) = ty else {
+        return None;
+    };
+    let seg = p.path.segments.last()?;
+    if seg.ident != wrapper {
+        return None;
+    }
+    match &seg.arguments {
+        syn::PathArguments::AngleBracketed(args,) => match args.args.first()? {
+            syn::GenericArgument::Type(inner,) => Some(inner,),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The `, ctx: &ContextRow` parameter and `ctx` argument that thread a repo's `#[from_context]`
+/// columns through the insert helpers; both empty when it has none.
+pub fn context_param(ast: &DeriveInput,) -> (Body, Body,) {
+    let has_context = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) => {
+            fields.named.iter().any(|f| find_get_attr(f, "from_context",).is_some(),)
+        }
+        _ => false,
+    };
+    match has_context {
+        true => (quote! { , ctx: &ContextRow }, quote! { ctx },),
+        false => (quote! {}, quote! {},),
+    }
+}
+
+/// Whether `ty` is a `PhantomData<..>` marker, however it's pathed.
+fn is_phantom(ty: &syn::Type,) -> bool {
+    matches!(ty, syn::Type::Path(p) if p.path.segments.last().is_some_and(|s| s.ident == "PhantomData"))
+}
+
+/// `ast` without its `#[skip]` and `PhantomData` fields, for the generators that only deal in
+/// columns.
+pub fn without_skipped(ast: &DeriveInput,) -> DeriveInput {
+    let mut ast = ast.clone();
+    if let Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) = &mut ast.data {
+        fields.named = std::mem::take(&mut fields.named,)
+            .into_pairs()
+            .filter(|p| find_get_attr(p.value(), "skip",).is_none() && !is_phantom(&p.value().ty,),)
+            .collect();
+    }
+    ast
+}
+
+/// `ast` with every `#[readonly]` spelled `#[locked]`, so the generators only check the one name.
+pub fn readonly_as_locked(mut ast: DeriveInput,) -> DeriveInput {
+    if let Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) = &mut ast.data {
+        for attr in fields.named.iter_mut().flat_map(|f| f.attrs.iter_mut(),) {
+            if attr.path().is_ident("readonly",) {
+                *attr = syn::parse_quote!(#[locked]);
+            }
+        }
+    }
+    ast
+}
+
+/// The generators all need named fields; anything else gets a single error naming what was found.
+pub fn validate_named_struct(ast: &DeriveInput,) -> Result<(), syn::Error,> {
+    let kind = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(_,), .. },) => return Ok((),),
+        Data::Struct(DataStruct { fields: Fields::Unnamed(_,), .. },) => "tuple struct",
+        Data::Struct(DataStruct { fields: Fields::Unit, .. },) => "unit struct",
+        Data::Enum(_,) => "enum",
+        Data::Union(_,) => "union",
+    };
+    Err(syn::Error::new_spanned(
+        &ast.ident,
+        format!("MaeRepo supports only structs with named fields; found {}", kind),
+    ),)
+}
+
+/// Rejects fields carrying attribute combinations that contradict each other, e.g. a column that
+/// is both `#[insert_only]` and `#[update_only]` would end up in neither row.
+pub fn validate_field_attrs(ast: &DeriveInput,) -> Result<(), syn::Error,> {
+    const CONFLICTS: [(&str, &str,); 5] = [
+        ("insert_only", "update_only",),
+        ("locked", "insert_only",),
+        ("locked", "update_only",),
+        ("from_context", "update_only",),
+        // an unset value would be both NULL and the SQL default
+        ("nullable", "default",),
+    ];
+
+    let Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) = &ast.data else {
+        return Ok((),);
+    };
+
+    let mut errors: Option<syn::Error,> = None;
+    let mut push = |e: syn::Error| match errors.as_mut() {
+        Some(errors,) => errors.combine(e,),
+        None => errors = Some(e,),
+    };
+    let mut columns = std::collections::HashSet::new();
+    for f in fields.named.iter() {
+        for attr in ["column", "default",] {
+            if let Err(e,) = find_get_attr_with_args(f, attr,) {
+                push(e,);
+            }
+        }
+        if find_get_attr(f, "nullable",).is_some() && option_inner(&f.ty,).is_none() {
+            push(syn::Error::new_spanned(&f.ty, "#[nullable] fields must be `Option<T>`",),);
+        }
+        if let (Ok(Some((_, column,),),), Some(rename,),) =
+            (find_get_attr_with_args(f, "column",), sqlx_rename(f,),)
+            && column != rename
+        {
+            push(syn::Error::new_spanned(
+                f,
+                format!("#[column(\"{}\")] and #[sqlx(rename = \"{}\")] disagree", column, rename),
+            ),);
+        }
+        // e.g. two fields #[column(...)]-renamed onto the same column
+        let column = column_name(f,);
+        if !columns.insert(column.clone(),) {
+            push(syn::Error::new_spanned(f, format!("duplicate column name `{}`", column),),);
+        }
+        for (a, b,) in CONFLICTS {
+            if find_get_attr(f, a,).is_some() && find_get_attr(f, b,).is_some() {
+                push(syn::Error::new_spanned(
+                    f,
+                    format!("a field can't be both #[{}] and #[{}]", a, b),
+                ),);
+            }
+        }
+    }
+
+    match errors {
+        Some(e,) => Err(e,),
+        None => Ok((),),
+    }
+}
+
+/// Columns written by `InsertRow`, in bind order.
+fn insert_columns(fields: &syn::punctuated::Punctuated<Field, syn::Token![,],>,) -> Vec<String,> {
+    fields
+        .iter()
+        .filter(|f| {
+            find_get_attr(f, "locked",).is_none()
+                && find_get_attr(f, "update_only",).is_none()
+                && find_get_attr(f, "from_context",).is_none()
+        },)
+        .filter(|f| f.ident.is_some(),)
+        .map(column_name,)
+        .collect()
+}
+
+/// The SQL column for a field: its `#[column("...")]` override, else the `#[sqlx(rename = "...")]`
+/// sqlx maps it with, else the field name. Malformed overrides are reported by
+/// `validate_field_attrs`.
+fn column_name(field: &Field,) -> String {
+    match find_get_attr_with_args(field, "column",) {
+        Ok(Some((_, name,),),) => name,
+        _ => sqlx_rename(field,)
+            .unwrap_or_else(|| field.ident.as_ref().map(|i| i.to_string(),).unwrap_or_default(),),
+    }
+}
+
+/// The value of `#[sqlx(rename = "...")]`, possibly next to other sqlx options.
+fn sqlx_rename(field: &Field,) -> Option<String,> {
+    field.attrs.iter().filter(|a| a.path().is_ident("sqlx",),).find_map(|a| {
+        a.parse_args_with(
+            syn::punctuated::Punctuated::<syn::Meta, syn::Token![,],>::parse_terminated,
+        )
+        .ok()?
+        .into_iter()
+        .find_map(|m| match m {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                path,
+                value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit,), .. },),
+                ..
+            },) if path.is_ident("rename",) => Some(lit.value(),),
+            _ => None,
+        },)
+    },)
+}
+
 // Utils to find various attributes
 fn find_get_attr(field: &Field, attr_name: &'static str,) -> Option<syn::Ident,> {
     let Some(ident,) = field.ident.clone() else {
@@ -340,3 +2149,19 @@ fn find_get_attr_with_args(
 
     Ok(None,)
 }
+
+fn find_struct_attr_with_args(
+    ast: &DeriveInput,
+    attr_name: &'static str,
+) -> Result<Option<String,>, syn::Error,> {
+    for attr in &ast.attrs {
+        if attr.path().is_ident(attr_name,) {
+            let lit: LitStr = attr.parse_args().map_err(|_| {
+                syn::Error::new_spanned(attr, format!("expected #[{}(\"...\")]", attr_name),)
+            },)?;
+            return Ok(Some(lit.value(),),);
+        }
+    }
+
+    Ok(None,)
+}
@@ -0,0 +1,127 @@
+//! Minimal stand-in for the actix-web pieces `#[run_app]` wires together. The `App` records its
+//! builder chain so an expansion can be checked without binding a server.
+
+#[derive(Clone, Default,)]
+pub struct PgPool;
+
+#[derive(Clone,)]
+pub struct SecretString(pub String,);
+
+pub struct TcpListener;
+
+pub struct ApplicationBaseUrl(pub String,);
+
+pub struct HmacSecret(pub SecretString,);
+
+#[derive(Default,)]
+pub struct TracingLogger;
+
+pub struct Cors;
+
+impl Cors {
+    pub fn permissive() -> Self {
+        Self
+    }
+}
+
+#[derive(Default,)]
+pub struct Compress;
+
+pub mod anyhow {
+    #[derive(Debug,)]
+    pub struct Error(pub String,);
+
+    impl From<std::io::Error,> for Error {
+        fn from(e: std::io::Error,) -> Self {
+            Self(e.to_string(),)
+        }
+    }
+}
+
+pub mod app {
+    use super::{SecretString, anyhow};
+
+    #[derive(Clone,)]
+    pub struct RedisStore;
+
+    pub struct SessionMiddleware;
+
+    pub async fn redis_session(_: SecretString,) -> Result<RedisStore, anyhow::Error,> {
+        Ok(RedisStore,)
+    }
+
+    pub fn session_middleware(_: SecretString, _: RedisStore,) -> SessionMiddleware {
+        SessionMiddleware
+    }
+}
+
+pub mod web {
+    pub struct Data<T,>(pub T,);
+
+    impl<T,> Data<T,> {
+        pub fn new(v: T,) -> Self {
+            Self(v,)
+        }
+    }
+}
+
+/// Short type name, e.g. `TracingLogger` for `common::actix::TracingLogger`.
+fn short_name<T,>() -> String {
+    let full = std::any::type_name::<T,>();
+    let base = full.split('<',).next().unwrap_or(full,);
+    base.rsplit("::",).next().unwrap_or(base,).to_string()
+}
+
+#[derive(Default,)]
+pub struct ActixWebApp {
+    pub calls: Vec<String,>,
+}
+
+impl ActixWebApp {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn wrap<M,>(mut self, _: M,) -> Self {
+        self.calls.push(format!("wrap({})", short_name::<M,>()),);
+        self
+    }
+
+    pub fn app_data<T,>(mut self, _: web::Data<T,>,) -> Self {
+        self.calls.push(format!("app_data({})", short_name::<T,>()),);
+        self
+    }
+
+    pub fn service(mut self, path: &str,) -> Self {
+        self.calls.push(format!("service({})", path),);
+        self
+    }
+
+    pub fn configure(mut self, f: fn(&mut Vec<String,>,),) -> Self {
+        f(&mut self.calls,);
+        self
+    }
+}
+
+pub struct HttpServer {
+    app: ActixWebApp,
+}
+
+impl HttpServer {
+    /// Builds the app once, as actix would per worker.
+    pub fn new<F: Fn() -> ActixWebApp,>(factory: F,) -> Self {
+        Self { app: factory(), }
+    }
+
+    pub fn listen(self, _: TcpListener,) -> std::io::Result<Self,> {
+        Ok(self,)
+    }
+
+    pub fn run(self,) -> Server {
+        Server { calls: self.app.calls, }
+    }
+}
+
+pub struct Server {
+    pub calls: Vec<String,>,
+}
@@ -0,0 +1,63 @@
+//! Setup and teardown hooks called by `#[mae_test]` around test bodies.
+
+use std::{
+    cell::{Cell, RefCell},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+thread_local! {
+    /// Hooks run on the test's thread, in order; read by the ordering tests.
+    pub static STAGES: RefCell<Vec<&'static str,>,> = const { RefCell::new(Vec::new(),) };
+    /// Makes `setup` panic on this thread.
+    pub static SETUP_PANICS: Cell<bool,> = const { Cell::new(false,) };
+}
+
+pub fn record_stage(stage: &'static str,) {
+    STAGES.with(|s| s.borrow_mut().push(stage,),);
+}
+
+/// Seeds per-test state; the value is bound as `setup` in the test body.
+pub async fn setup() -> u32 {
+    if SETUP_PANICS.with(Cell::get,) {
+        panic!("setup failed");
+    }
+    record_stage("setup",);
+    42
+}
+
+pub async fn named_setup() -> &'static str {
+    record_stage("named setup",);
+    "named"
+}
+
+pub static TEARDOWNS: AtomicUsize = AtomicUsize::new(0,);
+
+pub async fn teardown() {
+    TEARDOWNS.fetch_add(1, Ordering::SeqCst,);
+    record_stage("teardown",);
+}
+
+pub static RECORDED_TEARDOWNS: AtomicUsize = AtomicUsize::new(0,);
+
+pub async fn failing_teardown() {
+    panic!("failing teardown");
+}
+
+pub async fn recording_teardown() {
+    RECORDED_TEARDOWNS.fetch_add(1, Ordering::SeqCst,);
+}
+
+pub static KEEP_ON_FAIL_TEARDOWNS: AtomicUsize = AtomicUsize::new(0,);
+
+pub async fn keep_on_fail_teardown() {
+    KEEP_ON_FAIL_TEARDOWNS.fetch_add(1, Ordering::SeqCst,);
+}
+
+/// Needs the `LocalSet` the body ran in, so it only works under `#[mae_test(local)]`.
+pub async fn local_teardown() {
+    let stage = std::rc::Rc::new("local teardown",);
+    let handle = super::tokio::task::spawn_local(async move { *stage },);
+    if let Ok(stage,) = handle.await {
+        record_stage(stage,);
+    }
+}
@@ -0,0 +1,51 @@
+//! Minimal stand-in for the `mae` runtime crate so derive output can be compiled and exercised.
+
+pub mod repo {
+    pub mod default {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Default,)]
+        pub enum DomainStatus {
+            #[default]
+            Active,
+            Inactive,
+        }
+
+        // serde's derive isn't available offline; these match its output for a unit enum
+        impl serde::Serialize for DomainStatus {
+            fn serialize<S: serde::Serializer,>(&self, s: S,) -> Result<S::Ok, S::Error,> {
+                match self {
+                    Self::Active => s.serialize_unit_variant("DomainStatus", 0, "Active",),
+                    Self::Inactive => s.serialize_unit_variant("DomainStatus", 1, "Inactive",),
+                }
+            }
+        }
+
+        impl<'de,> serde::Deserialize<'de,> for DomainStatus {
+            fn deserialize<D: serde::Deserializer<'de,>,>(d: D,) -> Result<Self, D::Error,> {
+                match String::deserialize(d,)?.as_str() {
+                    "Active" => Ok(Self::Active,),
+                    "Inactive" => Ok(Self::Inactive,),
+                    other => {
+                        Err(serde::de::Error::unknown_variant(other, &["Active", "Inactive",],),)
+                    }
+                }
+            }
+        }
+    }
+
+    pub mod __private__ {
+        pub type AsSqlParts = (Vec<String,>, Option<Vec<String,>,>,);
+
+        pub trait ToSqlParts {
+            fn to_sql_parts(&self,) -> AsSqlParts;
+        }
+
+        pub trait BindArgs {
+            fn bind(&self, args: &mut crate::common::sqlx::postgres::PgArguments,);
+            fn bind_len(&self,) -> usize;
+        }
+
+        pub trait Build<Ctx: Clone + Send + 'static, I, U, F, P,> {
+            fn schema() -> String;
+        }
+    }
+}
@@ -0,0 +1,31 @@
+//! Process-global registry the default `#[mae_test(metrics)]` recorder writes to.
+
+use std::{
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+#[derive(Debug, Clone,)]
+pub struct TestMetric {
+    pub name: &'static str,
+    pub elapsed: Duration,
+    pub passed: bool,
+}
+
+static METRICS: OnceLock<Mutex<Vec<TestMetric,>,>,> = OnceLock::new();
+
+pub fn metrics() -> Vec<TestMetric,> {
+    METRICS.get_or_init(Default::default,).lock().map(|m| m.clone(),).unwrap_or_default()
+}
+
+pub fn record_metric(name: &'static str, elapsed: Duration, passed: bool,) {
+    if let Ok(mut m,) = METRICS.get_or_init(Default::default,).lock() {
+        m.push(TestMetric { name, elapsed, passed, },);
+    }
+}
+
+pub fn print_metrics() {
+    for m in metrics() {
+        eprintln!("{} {:?} {}", m.name, m.elapsed, if m.passed { "ok" } else { "FAILED" });
+    }
+}
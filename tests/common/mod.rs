@@ -1 +1,11 @@
+pub mod actix;
+pub mod context;
+pub mod mae;
+pub mod metrics;
 pub mod must;
+pub mod rand;
+pub mod serde;
+pub mod sqlx;
+pub mod tokio;
+pub mod tracing;
+pub mod tracing_subscriber;
@@ -1,5 +1,5 @@
-#[cfg(test)]
-use pretty_assertions::{assert_eq, assert_ne};
+// `must_eq`/`must_ne` use std's assert_eq!/assert_ne!: pretty_assertions was never declared as a
+// dependency, so its import couldn't resolve once these helpers were compiled with the tests.
 use std::panic::Location;
 
 /// Trait for safe test assertions on `Option` and `Result`.
@@ -57,6 +57,70 @@ impl<T, E: std::fmt::Debug,> MustExpect<T,> for Result<T, E,> {
     }
 }
 
+/// Trait for asserting a `Result` failed, returning the error.
+#[cfg(test)]
+pub trait MustErr<E,> {
+    /// Panics with the unexpected `Ok` value if the result succeeded.
+    #[track_caller]
+    fn must_err(self,) -> E;
+
+    /// Like `must_err`, but also panics with the error when `pred` rejects it, e.g. to pin an
+    /// error variant.
+    #[track_caller]
+    fn must_err_matching(self, pred: impl Fn(&E,) -> bool,) -> E
+    where
+        E: std::fmt::Debug;
+}
+
+#[cfg(test)]
+impl<T: std::fmt::Debug, E,> MustErr<E,> for Result<T, E,> {
+    #[track_caller]
+    fn must_err(self,) -> E {
+        match self {
+            Ok(v,) => panic!(
+                "test invariant failed: expected Err, got Ok({:?}) at {}",
+                v,
+                Location::caller()
+            ),
+            Err(e,) => e,
+        }
+    }
+
+    #[track_caller]
+    fn must_err_matching(self, pred: impl Fn(&E,) -> bool,) -> E
+    where
+        E: std::fmt::Debug,
+    {
+        let e = self.must_err();
+        if !pred(&e,) {
+            panic!("test invariant failed: Err({:?}) does not match at {}", e, Location::caller())
+        }
+        e
+    }
+}
+
+/// Trait for asserting an `Option` is empty.
+#[cfg(test)]
+pub trait MustNone {
+    /// Panics with the unexpected value if the option is `Some`.
+    #[track_caller]
+    fn must_none(self,);
+}
+
+#[cfg(test)]
+impl<T: std::fmt::Debug,> MustNone for Option<T,> {
+    #[track_caller]
+    fn must_none(self,) {
+        if let Some(v,) = self {
+            panic!(
+                "test invariant failed: expected None, got Some({:?}) at {}",
+                v,
+                Location::caller()
+            )
+        }
+    }
+}
+
 // ── Convenience free functions ──────────────────────────────────────────────
 
 #[cfg(test)]
@@ -71,6 +135,27 @@ pub fn must_be_ok<T, E: std::fmt::Debug,>(res: Result<T, E,>,) -> T {
     res.must()
 }
 
+#[cfg(test)]
+#[track_caller]
+pub fn must_be_err<T: std::fmt::Debug, E,>(res: Result<T, E,>,) -> E {
+    res.must_err()
+}
+
+#[cfg(test)]
+#[track_caller]
+pub fn must_be_err_matching<T: std::fmt::Debug, E: std::fmt::Debug,>(
+    res: Result<T, E,>,
+    pred: impl Fn(&E,) -> bool,
+) -> E {
+    res.must_err_matching(pred,)
+}
+
+#[cfg(test)]
+#[track_caller]
+pub fn must_be_none<T: std::fmt::Debug,>(opt: Option<T,>,) {
+    opt.must_none()
+}
+
 #[cfg(test)]
 #[track_caller]
 pub fn must_expect_some<T,>(opt: Option<T,>, msg: &str,) -> T {
@@ -83,12 +168,235 @@ pub fn must_expect_ok<T, E: std::fmt::Debug,>(res: Result<T, E,>, msg: &str,) ->
     res.must_expect(msg,)
 }
 
+/// Like `must_be_ok`, but includes the attempted SQL in the panic message so repo-test failures
+/// show which query failed. Generic over the error so it works with `sqlx::Error`.
+#[cfg(test)]
+#[track_caller]
+pub fn must_ok_sqlx<T, E: std::fmt::Debug,>(res: Result<T, E,>, sql: &str,) -> T {
+    res.unwrap_or_else(|err| {
+        panic!(
+            "test invariant failed: expected Ok, got {:?}\n\tsql: {}\nat {}",
+            err,
+            sql,
+            Location::caller()
+        )
+    },)
+}
+
+/// Panics unless every key/value in `expected_subset` is present and equal in `actual`; extra
+/// keys in `actual` are ignored. Objects are compared recursively, everything else by equality.
+#[cfg(test)]
+#[track_caller]
+pub fn must_json_contains(actual: &serde_json::Value, expected_subset: &serde_json::Value,) {
+    if let Err(msg,) = json_subset_mismatch(actual, expected_subset, String::new(),) {
+        panic!("json subset mismatch: {} at {}", msg, Location::caller())
+    }
+}
+
+#[cfg(test)]
+fn json_subset_mismatch(
+    actual: &serde_json::Value,
+    expected: &serde_json::Value,
+    path: String,
+) -> Result<(), String,> {
+    match (actual, expected,) {
+        (serde_json::Value::Object(actual,), serde_json::Value::Object(expected,),) => {
+            expected.iter().try_for_each(|(key, expected,)| {
+                let path = format!("{}/{}", path, key);
+                match actual.get(key,) {
+                    Some(actual,) => json_subset_mismatch(actual, expected, path,),
+                    None => Err(format!("missing key `{}`", path),),
+                }
+            },)
+        }
+        _ if actual == expected => Ok((),),
+        _ => Err(format!(
+            "`{}` expected {}, got {}",
+            if path.is_empty() { "/" } else { &path },
+            expected,
+            actual
+        ),),
+    }
+}
+
+/// Panics unless `left` and `right` are structurally equal, objects compared as unordered maps.
+/// The message names the first differing path, e.g. `$.a.b[2]`.
+#[cfg(test)]
+#[track_caller]
+pub fn must_json_eq(left: &serde_json::Value, right: &serde_json::Value,) {
+    if let Err(msg,) = json_mismatch(left, right, "$".to_string(),) {
+        panic!("json mismatch: {} at {}", msg, Location::caller())
+    }
+}
+
+#[cfg(test)]
+fn json_mismatch(
+    left: &serde_json::Value,
+    right: &serde_json::Value,
+    path: String,
+) -> Result<(), String,> {
+    use serde_json::Value;
+
+    match (left, right,) {
+        (Value::Object(left,), Value::Object(right,),) => {
+            let mut keys = left.keys().chain(right.keys(),).collect::<Vec<_,>>();
+            keys.sort();
+            keys.dedup();
+            keys.into_iter().try_for_each(|key| {
+                let path = format!("{}.{}", path, key);
+                match (left.get(key,), right.get(key,),) {
+                    (Some(l,), Some(r,),) => json_mismatch(l, r, path,),
+                    (Some(_,), None,) => Err(format!("`{}` only on the left", path),),
+                    (None, _,) => Err(format!("`{}` only on the right", path),),
+                }
+            },)
+        }
+        (Value::Array(l,), Value::Array(r,),) if l.len() == r.len() => l
+            .iter()
+            .zip(r,)
+            .enumerate()
+            .try_for_each(|(i, (l, r,),)| json_mismatch(l, r, format!("{}[{}]", path, i),),),
+        _ if left == right => Ok((),),
+        _ => Err(format!("`{}`: {} != {}", path, left, right),),
+    }
+}
+
+/// Haystacks searchable by `must_contains`: substrings of `str`/`String`, elements of slices/`Vec`.
+#[cfg(test)]
+pub trait Haystack<N: ?Sized,> {
+    fn has(&self, needle: &N,) -> bool;
+}
+
+#[cfg(test)]
+impl Haystack<str,> for str {
+    fn has(&self, needle: &str,) -> bool {
+        self.contains(needle,)
+    }
+}
+
+#[cfg(test)]
+impl Haystack<str,> for String {
+    fn has(&self, needle: &str,) -> bool {
+        self.contains(needle,)
+    }
+}
+
+#[cfg(test)]
+impl<T: PartialEq,> Haystack<T,> for [T] {
+    fn has(&self, needle: &T,) -> bool {
+        self.contains(needle,)
+    }
+}
+
+#[cfg(test)]
+impl<T: PartialEq,> Haystack<T,> for Vec<T,> {
+    fn has(&self, needle: &T,) -> bool {
+        self.as_slice().has(needle,)
+    }
+}
+
+/// Panics, showing both operands, unless `haystack` contains `needle`.
+#[cfg(test)]
+#[track_caller]
+pub fn must_contains<H, N,>(haystack: &H, needle: &N,)
+where
+    H: Haystack<N,> + std::fmt::Debug + ?Sized,
+    N: std::fmt::Debug + ?Sized,
+{
+    if !haystack.has(needle,) {
+        panic!(
+            "test invariant failed: {:?} does not contain {:?} at {}",
+            haystack,
+            needle,
+            Location::caller()
+        )
+    }
+}
+
+/// Panics, showing the value, unless `pattern` accepts it. Returns the value for further checks.
+#[cfg(test)]
+#[track_caller]
+pub fn must_matches<T: std::fmt::Debug,>(value: T, pattern: impl FnOnce(&T,) -> bool,) -> T {
+    if !pattern(&value,) {
+        panic!("test invariant failed: {:?} does not match at {}", value, Location::caller())
+    }
+    value
+}
+
+/// Runs `f` and returns its value, panicking with the captured panic message if it panicked.
+#[cfg(test)]
+#[track_caller]
+pub fn must_no_panic<T,>(f: impl FnOnce() -> T + std::panic::UnwindSafe,) -> T {
+    std::panic::catch_unwind(f,).unwrap_or_else(|payload| {
+        let msg = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string(),)
+            .or_else(|| payload.downcast_ref::<String>().cloned(),)
+            .unwrap_or_else(|| "<non-string panic payload>".to_string(),);
+        panic!(
+            "test invariant failed: expected no panic, got panic `{}` at {}",
+            msg,
+            Location::caller()
+        )
+    },)
+}
+
+/// Panics unless `left` and `right` are at most `epsilon` apart.
+#[cfg(test)]
+#[track_caller]
+// negated so a NaN anywhere fails instead of comparing false
+#[allow(clippy::neg_cmp_op_on_partial_ord)]
+pub fn must_eq_within(left: f64, right: f64, epsilon: f64,) {
+    if !((left - right).abs() <= epsilon) {
+        panic!(
+            "test invariant failed: {} and {} differ by more than {} at {}",
+            left,
+            right,
+            epsilon,
+            Location::caller()
+        )
+    }
+}
+
+/// Panics unless `left` and `right` are at most `epsilon` apart.
+#[cfg(test)]
+#[track_caller]
+pub fn must_eq_duration_within(
+    left: std::time::Duration,
+    right: std::time::Duration,
+    epsilon: std::time::Duration,
+) {
+    if left.abs_diff(right,) > epsilon {
+        panic!(
+            "test invariant failed: {:?} and {:?} differ by more than {:?} at {}",
+            left,
+            right,
+            epsilon,
+            Location::caller()
+        )
+    }
+}
+
 #[allow(clippy::disallowed_methods)]
 #[track_caller]
 pub fn must_eq<V: PartialEq + std::fmt::Debug,>(left: V, right: V,) {
     assert_eq!(left, right);
 }
 
+/// Like `must_eq`, with `msg` in the panic, e.g. to name the case a loop was on.
+#[track_caller]
+pub fn must_eq_ctx<V: PartialEq + std::fmt::Debug,>(left: V, right: V, msg: &str,) {
+    if left != right {
+        panic!(
+            "test invariant failed: {}: {:?} != {:?} at {}",
+            msg,
+            left,
+            right,
+            Location::caller()
+        )
+    }
+}
+
 #[track_caller]
 pub fn must_ne<V: PartialEq + std::fmt::Debug,>(left: V, right: V,) {
     assert_ne!(left, right);
@@ -163,12 +471,241 @@ mod tests {
         must_eq(a + b + c + d, 10,);
     }
 
+    #[test]
+    fn result_must_err_returns_error() {
+        let r: Result<i32, &str,> = Err("boom",);
+        must_eq(r.must_err(), "boom",);
+        must_eq(must_be_err(Err::<i32, &str,>("bang",),), "bang",);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected Err, got Ok(7)")]
+    fn result_must_err_panics_on_ok() {
+        let _ = Ok::<i32, &str,>(7,).must_err();
+    }
+
+    #[derive(Debug, PartialEq,)]
+    enum LoadError {
+        NotFound(i32,),
+        Forbidden,
+    }
+
+    #[test]
+    fn result_must_err_matching_returns_matching_error() {
+        let r: Result<(), LoadError,> = Err(LoadError::NotFound(7,),);
+        let e = r.must_err_matching(|e| matches!(e, LoadError::NotFound(_)),);
+        must_eq(e, LoadError::NotFound(7,),);
+
+        let r: Result<(), &str,> = Err("relation \"users\" does not exist",);
+        must_be_err_matching(r, |e| e.contains("users",),);
+    }
+
+    #[test]
+    #[should_panic(expected = "Err(Forbidden) does not match")]
+    fn result_must_err_matching_rejects_other_variant() {
+        let r: Result<(), LoadError,> = Err(LoadError::Forbidden,);
+        let _ = must_be_err_matching(r, |e| matches!(e, LoadError::NotFound(_)),);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected Err, got Ok(1)")]
+    fn result_must_err_matching_panics_on_ok() {
+        let _ = Ok::<i32, LoadError,>(1,).must_err_matching(|_| true,);
+    }
+
+    #[test]
+    fn option_must_none_ok() {
+        None::<i32,>.must_none();
+        must_be_none(None::<&str,>,);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected None, got Some(\"leftover\")")]
+    fn option_must_none_panics_on_some() {
+        must_be_none(Some("leftover",),);
+    }
+
+    #[test]
+    fn must_ok_sqlx_ok() {
+        let v = must_ok_sqlx(Ok::<i32, &str,>(5,), "SELECT 5",);
+        must_eq(v, 5,);
+    }
+
+    #[test]
+    #[should_panic(expected = "sql: SELECT id FROM accounts WHERE id = $1")]
+    fn must_ok_sqlx_panics_with_sql() {
+        let r: Result<i32, &str,> = Err("relation does not exist",);
+        let _ = must_ok_sqlx(r, "SELECT id FROM accounts WHERE id = $1",);
+    }
+
+    fn json(s: &str,) -> serde_json::Value {
+        must_be_ok(serde_json::from_str(s,),)
+    }
+
+    #[test]
+    fn must_json_contains_ignores_extra_keys() {
+        must_json_contains(
+            &json(r#"{ "meta": { "source": "import", "rev": 2 }, "flag": true }"#,),
+            &json(r#"{ "meta": { "source": "import" } }"#,),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "missing key `/meta/owner`")]
+    fn must_json_contains_missing_key_panics() {
+        must_json_contains(
+            &json(r#"{ "meta": { "source": "import" } }"#,),
+            &json(r#"{ "meta": { "owner": 1 } }"#,),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "`/meta/source` expected \"api\", got \"import\"")]
+    fn must_json_contains_mismatched_value_panics() {
+        must_json_contains(
+            &json(r#"{ "meta": { "source": "import" } }"#,),
+            &json(r#"{ "meta": { "source": "api" } }"#,),
+        );
+    }
+
+    #[test]
+    fn must_json_eq_ignores_key_order() {
+        must_json_eq(
+            &json(r#"{ "a": 1, "b": { "c": [1, 2], "d": null } }"#,),
+            &json(r#"{ "b": { "d": null, "c": [1, 2] }, "a": 1 }"#,),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "json mismatch: `$.a.b[2]`: 3 != 4")]
+    fn must_json_eq_names_differing_path() {
+        must_json_eq(
+            &json(r#"{ "a": { "b": [1, 2, 3] } }"#,),
+            &json(r#"{ "a": { "b": [1, 2, 4] } }"#,),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "`$.extra` only on the right")]
+    fn must_json_eq_names_missing_key() {
+        must_json_eq(&json(r#"{ "a": 1 }"#,), &json(r#"{ "a": 1, "extra": true }"#,),);
+    }
+
+    #[test]
+    fn must_contains_substring() {
+        must_contains("hello world", "world",);
+        must_contains(&"hello".to_string(), "ell",);
+    }
+
+    #[test]
+    #[should_panic(expected = "\"hello world\" does not contain \"moon\"")]
+    fn must_contains_substring_miss_panics() {
+        must_contains("hello world", "moon",);
+    }
+
+    #[test]
+    fn must_contains_element() {
+        must_contains(&vec![1, 2, 3], &2,);
+        must_contains(&["a", "b",][..], &"b",);
+    }
+
+    #[test]
+    #[should_panic(expected = "[1, 2, 3] does not contain 9")]
+    fn must_contains_element_miss_panics() {
+        must_contains(&vec![1, 2, 3], &9,);
+    }
+
+    #[test]
+    fn must_matches_predicate() {
+        let v = must_matches("id-42", |s| s.starts_with("id-",),);
+        must_eq(v, "id-42",);
+    }
+
+    #[test]
+    #[should_panic(expected = "7 does not match")]
+    fn must_matches_predicate_miss_panics() {
+        must_matches(7, |n| n % 2 == 0,);
+    }
+
+    #[test]
+    fn must_eq_within_accepts_exact_epsilon() {
+        must_eq_within(1.0, 1.5, 0.5,);
+        must_eq_within(1.5, 1.0, 0.5,);
+    }
+
+    #[test]
+    #[should_panic(expected = "1 and 1.5 differ by more than 0.49")]
+    fn must_eq_within_panics_just_over() {
+        must_eq_within(1.0, 1.5, 0.49,);
+    }
+
+    #[test]
+    #[should_panic(expected = "NaN and 1 differ by more than 0.1")]
+    fn must_eq_within_panics_on_nan_left() {
+        must_eq_within(f64::NAN, 1.0, 0.1,);
+    }
+
+    #[test]
+    #[should_panic(expected = "1 and 1 differ by more than NaN")]
+    fn must_eq_within_panics_on_nan_epsilon() {
+        must_eq_within(1.0, 1.0, f64::NAN,);
+    }
+
+    #[test]
+    fn must_eq_duration_within_accepts_exact_epsilon() {
+        use std::time::Duration;
+
+        must_eq_duration_within(
+            Duration::from_millis(100,),
+            Duration::from_millis(110,),
+            Duration::from_millis(10,),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "100ms and 110ms differ by more than 9.999999ms")]
+    fn must_eq_duration_within_panics_just_over() {
+        use std::time::Duration;
+
+        must_eq_duration_within(
+            Duration::from_millis(100,),
+            Duration::from_millis(110,),
+            Duration::from_millis(10,) - Duration::from_nanos(1,),
+        );
+    }
+
+    #[test]
+    fn must_no_panic_returns_value() {
+        must_eq(must_no_panic(|| 1 + 1,), 2,);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected no panic, got panic `kaboom`")]
+    fn must_no_panic_reports_payload() {
+        must_no_panic(|| panic!("kaboom"),);
+    }
+
     #[test]
     fn must_eq_and_ne() {
         must_eq(5, 5,);
         must_ne(5, 6,);
     }
 
+    #[test]
+    fn must_eq_ctx_passes_on_equal() {
+        for n in 0..3 {
+            must_eq_ctx(n * 2, n + n, &format!("case {}", n),);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "test invariant failed: case 2: 4 != 5 at ")]
+    fn must_eq_ctx_names_the_case() {
+        for (n, want,) in [(1, 2,), (2, 5,),] {
+            must_eq_ctx(n * 2, want, &format!("case {}", n),);
+        }
+    }
+
     #[test]
     fn must_be_true_works() {
         must_be_true(true,);
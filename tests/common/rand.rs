@@ -0,0 +1,37 @@
+//! Minimal stand-in for the `rand` traits and `StdRng` used by `#[arbitrary]` and
+//! `#[mae_test(seed)]`.
+
+pub trait RngCore {
+    fn next_u64(&mut self,) -> u64;
+}
+
+pub trait Rng: RngCore {}
+
+impl<R: RngCore + ?Sized,> Rng for R {}
+
+pub trait SeedableRng: Sized {
+    fn seed_from_u64(state: u64,) -> Self;
+}
+
+pub mod rngs {
+    /// xorshift64; plenty for test data.
+    #[derive(Debug, Clone,)]
+    pub struct StdRng(u64,);
+
+    impl super::SeedableRng for StdRng {
+        fn seed_from_u64(state: u64,) -> Self {
+            Self(state | 1,)
+        }
+    }
+
+    impl super::RngCore for StdRng {
+        fn next_u64(&mut self,) -> u64 {
+            let mut x = self.0;
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            self.0 = x;
+            x
+        }
+    }
+}
@@ -0,0 +1,4 @@
+//! `serde` as `#[schema]` uses it: the real crate, with its derives taken from the stand-in crate.
+
+pub use ::serde::*;
+pub use derive_stubs::{Deserialize, Serialize};
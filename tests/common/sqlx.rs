@@ -0,0 +1,110 @@
+//! Minimal stand-in for the pieces of `sqlx` referenced by generated code.
+
+use std::{marker::PhantomData, sync::Mutex};
+
+pub use derive_stubs::FromRow;
+
+/// Every statement run through `query(..).execute(..)`, in order.
+pub static EXECUTED: Mutex<Vec<String,>,> = Mutex::new(Vec::new(),);
+
+/// Every statement fetched through `query_as`/`query_as_with`, with its bound values in order.
+pub static FETCHED: Mutex<Vec<(String, Vec<String,>,),>,> = Mutex::new(Vec::new(),);
+
+#[derive(Debug,)]
+pub struct Error(pub String,);
+
+pub type Result<T, E = Error,> = std::result::Result<T, E,>;
+
+/// Implemented by `#[derive(FromRow)]`; nothing is ever decoded, no rows come back.
+pub trait FromRow {}
+
+pub struct Postgres;
+
+pub struct PgPool;
+
+pub trait Arguments {
+    fn add<T: std::fmt::Debug,>(&mut self, value: T,) -> Result<(), String,>;
+}
+
+pub trait Connection: Sized {
+    fn connect(url: &str,) -> impl Future<Output = Result<Self, Error,>,>;
+}
+
+pub struct PgConnection;
+
+impl Connection for PgConnection {
+    async fn connect(_url: &str,) -> Result<Self, Error,> {
+        Ok(PgConnection,)
+    }
+}
+
+pub struct Query(String,);
+
+pub fn query(sql: &str,) -> Query {
+    Query(sql.to_string(),)
+}
+
+impl Query {
+    pub async fn execute(self, _conn: &mut PgConnection,) -> Result<(), Error,> {
+        EXECUTED.lock().map_err(|e| Error(e.to_string(),),)?.push(self.0,);
+        Ok((),)
+    }
+}
+
+pub struct QueryAs<DB, O,> {
+    sql: String,
+    args: postgres::PgArguments,
+    _row: PhantomData<(DB, O,),>,
+}
+
+pub fn query_as<DB, O: FromRow,>(sql: &str,) -> QueryAs<DB, O,> {
+    query_as_with(sql, postgres::PgArguments::default(),)
+}
+
+pub fn query_as_with<DB, O: FromRow, A: Into<postgres::PgArguments,>,>(
+    sql: &str,
+    args: A,
+) -> QueryAs<DB, O,> {
+    QueryAs { sql: sql.to_string(), args: args.into(), _row: PhantomData, }
+}
+
+impl<O: FromRow,> QueryAs<Postgres, O,> {
+    pub fn bind<T: std::fmt::Debug,>(mut self, value: T,) -> Self {
+        let _ = Arguments::add(&mut self.args, value,);
+        self
+    }
+
+    /// Records the statement and finds nothing.
+    pub async fn fetch_optional(self, _pool: &PgPool,) -> Result<Option<O,>,> {
+        self.record()?;
+        Ok(None,)
+    }
+
+    /// Records the statement and fails like sqlx's `RowNotFound`.
+    pub async fn fetch_one(self, _pool: &PgPool,) -> Result<O,> {
+        self.record()?;
+        Err(Error("no rows returned by a query that expected to return at least one row".into(),),)
+    }
+
+    fn record(self,) -> Result<(),> {
+        FETCHED.lock().map_err(|e| Error(e.to_string(),),)?.push((self.sql, self.args.values,),);
+        Ok((),)
+    }
+}
+
+pub mod postgres {
+    pub trait PgHasArrayType {}
+
+    /// Records the `Debug` form of every bound value, in bind order.
+    #[derive(Debug, Default,)]
+    pub struct PgArguments {
+        pub values: Vec<String,>,
+    }
+
+    impl super::Arguments for PgArguments {
+        fn add<T: std::fmt::Debug,>(&mut self, value: T,) -> Result<(), String,> {
+            self.values.push(format!("{:?}", value),);
+            Ok((),)
+        }
+    }
+}
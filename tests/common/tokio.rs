@@ -0,0 +1,238 @@
+//! Minimal stand-in for the tokio runtime API driven by `#[mae_test]`.
+
+pub mod runtime {
+    use std::{
+        future::Future,
+        pin::pin,
+        sync::Arc,
+        task::{Context, Poll, Wake, Waker},
+        thread::{self, Thread},
+    };
+
+    struct ThreadWaker(Thread,);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self,>,) {
+            self.0.unpark();
+        }
+    }
+
+    pub struct Runtime;
+
+    impl Runtime {
+        pub fn block_on<F: Future,>(&self, fut: F,) -> F::Output {
+            let mut fut = pin!(fut);
+            let waker = Waker::from(Arc::new(ThreadWaker(thread::current(),),),);
+            let mut cx = Context::from_waker(&waker,);
+            loop {
+                if let Poll::Ready(out,) = fut.as_mut().poll(&mut cx,) {
+                    return out;
+                }
+                thread::park();
+            }
+        }
+    }
+
+    thread_local! {
+        static BUILT: std::cell::Cell<Option<(&'static str, Option<usize,>,),>,> =
+            const { std::cell::Cell::new(None,) };
+    }
+
+    /// Flavor and worker count of the last runtime built on this thread.
+    pub fn last_built() -> Option<(&'static str, Option<usize,>,),> {
+        BUILT.with(std::cell::Cell::get,)
+    }
+
+    pub struct Builder {
+        flavor: &'static str,
+        worker_threads: Option<usize,>,
+    }
+
+    impl Builder {
+        pub fn new_multi_thread() -> Self {
+            Self { flavor: "multi_thread", worker_threads: None, }
+        }
+
+        pub fn new_current_thread() -> Self {
+            Self { flavor: "current_thread", worker_threads: None, }
+        }
+
+        pub fn worker_threads(&mut self, n: usize,) -> &mut Self {
+            self.worker_threads = Some(n,);
+            self
+        }
+
+        pub fn enable_all(&mut self,) -> &mut Self {
+            self
+        }
+
+        pub fn build(&mut self,) -> std::io::Result<Runtime,> {
+            BUILT.with(|b| b.set(Some((self.flavor, self.worker_threads,),),),);
+            Ok(Runtime,)
+        }
+    }
+}
+
+pub mod time {
+    use std::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    #[derive(Debug,)]
+    pub struct Elapsed;
+
+    /// Wakes `cx` once `deadline` has passed, from a helper thread.
+    fn wake_at(deadline: Instant, cx: &Context<'_,>,) {
+        let waker = cx.waker().clone();
+        thread::spawn(move || {
+            thread::sleep(deadline.saturating_duration_since(Instant::now(),),);
+            waker.wake();
+        },);
+    }
+
+    pub struct Sleep {
+        deadline: Instant,
+        armed: bool,
+    }
+
+    impl Future for Sleep {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self,>, cx: &mut Context<'_,>,) -> Poll<(),> {
+            if Instant::now() >= self.deadline {
+                return Poll::Ready((),);
+            }
+            if !self.armed {
+                self.armed = true;
+                wake_at(self.deadline, cx,);
+            }
+            Poll::Pending
+        }
+    }
+
+    pub fn sleep(duration: Duration,) -> Sleep {
+        Sleep { deadline: Instant::now() + duration, armed: false, }
+    }
+
+    pub async fn timeout<F: Future,>(duration: Duration, fut: F,) -> Result<F::Output, Elapsed,> {
+        let mut fut = std::pin::pin!(fut);
+        let mut deadline = std::pin::pin!(sleep(duration));
+        std::future::poll_fn(|cx| {
+            if let Poll::Ready(out,) = fut.as_mut().poll(cx,) {
+                return Poll::Ready(Ok(out,),);
+            }
+            match deadline.as_mut().poll(cx,) {
+                Poll::Ready((),) => Poll::Ready(Err(Elapsed,),),
+                Poll::Pending => Poll::Pending,
+            }
+        },)
+        .await
+    }
+}
+
+pub mod task {
+    use std::{
+        cell::{Cell, RefCell},
+        future::Future,
+        pin::Pin,
+        rc::Rc,
+        task::{Context, Poll, Waker},
+    };
+
+    type Task = Pin<Box<dyn Future<Output = (),>,>,>;
+    /// A finished task's output, and the waker of whoever awaits its `JoinHandle`.
+    type Slot<T,> = Rc<RefCell<(Option<T,>, Option<Waker,>,),>,>;
+
+    thread_local! {
+        static SPAWNED: RefCell<Vec<Task,>,> = const { RefCell::new(Vec::new(),) };
+        static IN_LOCAL_SET: Cell<bool,> = const { Cell::new(false,) };
+    }
+
+    #[derive(Debug,)]
+    pub struct JoinError;
+
+    pub struct JoinHandle<T,> {
+        slot: Slot<T,>,
+    }
+
+    impl<T,> Future for JoinHandle<T,> {
+        type Output = Result<T, JoinError,>;
+
+        fn poll(self: Pin<&mut Self,>, cx: &mut Context<'_,>,) -> Poll<Self::Output,> {
+            let mut slot = self.slot.borrow_mut();
+            match slot.0.take() {
+                Some(out,) => Poll::Ready(Ok(out,),),
+                None => {
+                    slot.1 = Some(cx.waker().clone(),);
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    /// Spawns a `!Send` future onto the `LocalSet` currently being driven on this thread.
+    pub fn spawn_local<F: Future + 'static,>(fut: F,) -> JoinHandle<F::Output,> {
+        if !IN_LOCAL_SET.with(Cell::get,) {
+            panic!("`spawn_local` called from outside of a `task::LocalSet`");
+        }
+        let slot: Slot<F::Output,> = Rc::new(RefCell::new((None, None,),),);
+        let task_slot = Rc::clone(&slot,);
+        SPAWNED.with(|s| {
+            s.borrow_mut().push(Box::pin(async move {
+                let out = fut.await;
+                let waker = {
+                    let mut slot = task_slot.borrow_mut();
+                    slot.0 = Some(out,);
+                    slot.1.take()
+                };
+                if let Some(waker,) = waker {
+                    waker.wake();
+                }
+            },),)
+        },);
+        JoinHandle { slot, }
+    }
+
+    #[derive(Default,)]
+    pub struct LocalSet {
+        tasks: RefCell<Vec<Task,>,>,
+    }
+
+    impl LocalSet {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        fn poll_tasks(&self, cx: &mut Context<'_,>,) {
+            loop {
+                let spawned = SPAWNED.with(|s| std::mem::take(&mut *s.borrow_mut(),),);
+                if spawned.is_empty() {
+                    break;
+                }
+                self.tasks.borrow_mut().extend(spawned,);
+                self.tasks.borrow_mut().retain_mut(|t| t.as_mut().poll(cx,).is_pending(),);
+            }
+        }
+
+        /// Drives `fut` to completion, polling tasks spawned with `spawn_local` alongside it.
+        pub async fn run_until<F: Future,>(&self, fut: F,) -> F::Output {
+            let mut fut = std::pin::pin!(fut);
+            std::future::poll_fn(|cx| {
+                let outer = IN_LOCAL_SET.with(|l| l.replace(true,),);
+                self.tasks.borrow_mut().retain_mut(|t| t.as_mut().poll(cx,).is_pending(),);
+                self.poll_tasks(cx,);
+                let out = fut.as_mut().poll(cx,);
+                if out.is_pending() && SPAWNED.with(|s| !s.borrow().is_empty(),) {
+                    cx.waker().wake_by_ref();
+                }
+                IN_LOCAL_SET.with(|l| l.set(outer,),);
+                out
+            },)
+            .await
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Minimal stand-in for `tracing` spans, tracking the name of the currently entered span.
+
+use std::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+thread_local! {
+    static CURRENT: RefCell<Vec<&'static str,>,> = const { RefCell::new(Vec::new(),) };
+}
+
+/// Name of the innermost span entered on this thread, if any.
+pub fn current_span() -> Option<&'static str,> {
+    CURRENT.with(|c| c.borrow().last().copied(),)
+}
+
+pub struct Span(pub &'static str,);
+
+macro_rules! info_span {
+    ($name:expr) => {
+        $crate::common::tracing::Span($name,)
+    };
+}
+pub(crate) use info_span;
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::common::tracing::subscriber::emit(
+            $crate::common::tracing::Level::INFO,
+            format!($($arg)*),
+        )
+    };
+}
+pub(crate) use info;
+
+pub trait Instrument: Future + Sized {
+    fn instrument(self, span: Span,) -> Instrumented<Self,> {
+        Instrumented { inner: Box::pin(self,), span, }
+    }
+}
+
+impl<F: Future,> Instrument for F {}
+
+pub struct Instrumented<F,> {
+    inner: Pin<Box<F,>,>,
+    span: Span,
+}
+
+impl<F: Future,> Future for Instrumented<F,> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self,>, cx: &mut Context<'_,>,) -> Poll<F::Output,> {
+        let name = self.span.0;
+        CURRENT.with(|c| c.borrow_mut().push(name,),);
+        let out = self.inner.as_mut().poll(cx,);
+        CURRENT.with(|c| c.borrow_mut().pop(),);
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord,)]
+pub struct Level(u8,);
+
+impl Level {
+    pub const TRACE: Level = Level(0,);
+    pub const DEBUG: Level = Level(1,);
+    pub const INFO: Level = Level(2,);
+    pub const WARN: Level = Level(3,);
+    pub const ERROR: Level = Level(4,);
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+        let name = ["TRACE", "DEBUG", "INFO", "WARN", "ERROR",];
+        f.write_str(name.get(self.0 as usize,).copied().unwrap_or("?",),)
+    }
+}
+
+pub mod subscriber {
+    use super::Level;
+    use std::{cell::Cell, sync::OnceLock};
+
+    static GLOBAL: OnceLock<Subscriber,> = OnceLock::new();
+
+    #[derive(Debug,)]
+    pub struct SetGlobalDefaultError;
+
+    pub fn set_global_default(subscriber: Subscriber,) -> Result<(), SetGlobalDefaultError,> {
+        GLOBAL.set(subscriber,).map_err(|_| SetGlobalDefaultError,)
+    }
+
+    /// Writes `msg` through the global subscriber, when one is set and `level` passes it.
+    pub fn emit(level: Level, msg: String,) {
+        if let Some(global,) = GLOBAL.get()
+            && global.test_writer
+            && level >= global.max_level
+        {
+            crate::common::tracing_subscriber::write_test_output(format!("{} {}", level, msg),);
+        }
+    }
+
+    thread_local! {
+        static DEFAULT: Cell<Option<Level,>,> = const { Cell::new(None,) };
+    }
+
+    /// Max level of the thread-default subscriber, if one is installed.
+    pub fn default_level() -> Option<Level,> {
+        DEFAULT.with(|d| d.get(),)
+    }
+
+    pub struct Subscriber {
+        pub max_level: Level,
+        pub test_writer: bool,
+    }
+
+    pub struct DefaultGuard(Option<Level,>,);
+
+    impl Drop for DefaultGuard {
+        fn drop(&mut self,) {
+            DEFAULT.with(|d| d.set(self.0,),);
+        }
+    }
+
+    pub fn set_default(subscriber: Subscriber,) -> DefaultGuard {
+        DefaultGuard(DEFAULT.with(|d| d.replace(Some(subscriber.max_level,),),),)
+    }
+}
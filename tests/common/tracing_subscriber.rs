@@ -0,0 +1,53 @@
+//! Minimal stand-in for the `tracing_subscriber::fmt()` builder.
+
+use super::tracing::{
+    Level,
+    subscriber::{SetGlobalDefaultError, Subscriber, set_global_default},
+};
+use std::sync::Mutex;
+
+/// Stands in for libtest's output capture, which `with_test_writer` writes through.
+static TEST_OUTPUT: Mutex<Vec<String,>,> = Mutex::new(Vec::new(),);
+
+/// Lines written through a test writer so far.
+pub fn test_output() -> Vec<String,> {
+    TEST_OUTPUT.lock().map(|o| o.clone(),).unwrap_or_default()
+}
+
+pub(crate) fn write_test_output(line: String,) {
+    if let Ok(mut output,) = TEST_OUTPUT.lock() {
+        output.push(line,);
+    }
+}
+
+pub struct FmtBuilder {
+    max_level: Level,
+    test_writer: bool,
+}
+
+pub fn fmt() -> FmtBuilder {
+    FmtBuilder { max_level: Level::INFO, test_writer: false, }
+}
+
+impl FmtBuilder {
+    pub fn with_max_level(self, max_level: Level,) -> Self {
+        Self { max_level, ..self }
+    }
+
+    pub fn with_test_writer(self,) -> Self {
+        Self { test_writer: true, ..self }
+    }
+
+    /// Installs the subscriber globally; errors when one is already set.
+    pub fn try_init(self,) -> Result<(), SetGlobalDefaultError,> {
+        set_global_default(self.finish(),)
+    }
+
+    pub fn with_writer<W,>(self, _writer: W,) -> Self {
+        self
+    }
+
+    pub fn finish(self,) -> Subscriber {
+        Subscriber { max_level: self.max_level, test_writer: self.test_writer, }
+    }
+}
@@ -0,0 +1,97 @@
+//! Stand-ins for the `sqlx::FromRow` and serde derives that `#[schema]` puts on the repo, so its
+//! output can be compiled in the integration tests without either crate's macros.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{Data, DataStruct, DeriveInput, Fields, Meta, Token, punctuated::Punctuated};
+
+/// Marks the type for the `sqlx` stand-in's `query_as`; registers `#[sqlx(...)]`.
+#[proc_macro_derive(FromRow, attributes(sqlx))]
+pub fn from_row(input: TokenStream,) -> TokenStream {
+    let ast = syn::parse_macro_input!(input as DeriveInput);
+    let ident = &ast.ident;
+    quote! { impl sqlx::FromRow for #ident {} }.into()
+}
+
+/// serde's output for a struct with named fields, honouring `#[serde(rename = "...")]`.
+#[proc_macro_derive(Serialize, attributes(serde))]
+pub fn serialize(input: TokenStream,) -> TokenStream {
+    let ast = syn::parse_macro_input!(input as DeriveInput);
+    let ident = &ast.ident;
+    let name = ident.to_string();
+    let fields = match named_fields(&ast,) {
+        Ok(fields,) => fields,
+        Err(e,) => return e.to_compile_error().into(),
+    };
+    let len = fields.len();
+    let entries = fields.iter().map(|(field, key,)| {
+        quote! { ::serde::ser::SerializeStruct::serialize_field(&mut row, #key, &self.#field)?; }
+    },);
+    quote! {
+        impl ::serde::Serialize for #ident {
+            fn serialize<S: ::serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                let mut row = ::serde::Serializer::serialize_struct(s, #name, #len)?;
+                #(#entries)*
+                ::serde::ser::SerializeStruct::end(row)
+            }
+        }
+    }
+    .into()
+}
+
+/// serde's output for a struct with named fields read from a json object; a missing key reads as
+/// `null`, so only `Option` fields may be left out.
+#[proc_macro_derive(Deserialize, attributes(serde))]
+pub fn deserialize(input: TokenStream,) -> TokenStream {
+    let ast = syn::parse_macro_input!(input as DeriveInput);
+    let ident = &ast.ident;
+    let fields = match named_fields(&ast,) {
+        Ok(fields,) => fields,
+        Err(e,) => return e.to_compile_error().into(),
+    };
+    let entries = fields.iter().map(|(field, key,)| {
+        quote! {
+            #field: ::serde_json::from_value(row.remove(#key).unwrap_or_default())
+                .map_err(<D::Error as ::serde::de::Error>::custom)?
+        }
+    },);
+    quote! {
+        impl<'de> ::serde::Deserialize<'de> for #ident {
+            fn deserialize<D: ::serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                let mut row =
+                    <::serde_json::Map<String, ::serde_json::Value> as ::serde::Deserialize>::deserialize(d)?;
+                Ok(Self { #(#entries,)* })
+            }
+        }
+    }
+    .into()
+}
+
+/// Each field with its json key: the `#[serde(rename)]` if any, else the field's name.
+fn named_fields(ast: &DeriveInput,) -> syn::Result<Vec<(syn::Ident, String,),>,> {
+    let Data::Struct(DataStruct { fields: Fields::Named(fields,), .. },) = &ast.data else {
+        return Err(syn::Error::new_spanned(&ast.ident, "expected a struct with named fields",),);
+    };
+    fields
+        .named
+        .iter()
+        .filter_map(|f| Some((f.ident.clone()?, f,),),)
+        .map(|(ident, f,)| {
+            let mut key = ident.to_string();
+            for attr in f.attrs.iter().filter(|a| a.path().is_ident("serde",),) {
+                let metas =
+                    attr.parse_args_with(Punctuated::<Meta, Token![,],>::parse_terminated,)?;
+                for meta in metas {
+                    if let Meta::NameValue(nv,) = meta
+                        && nv.path.is_ident("rename",)
+                        && let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit,), .. },) =
+                            nv.value
+                    {
+                        key = lit.value();
+                    }
+                }
+            }
+            Ok((ident, key,),)
+        },)
+        .collect()
+}
@@ -0,0 +1,334 @@
+#[allow(unused)]
+mod common;
+
+use common::{
+    must::*,
+    rand::{self, RngCore, SeedableRng, rngs::StdRng},
+    sqlx, tokio, tracing, tracing_subscriber,
+};
+use mae_macros::mae_test;
+
+#[mae_test]
+async fn runs_async_body() -> Result<(), String,> {
+    let v = async { 2 }.await;
+    must_eq(v, 2,);
+    Ok((),)
+}
+
+#[mae_test(span)]
+async fn span_wraps_body() {
+    must_eq(tracing::current_span(), Some("span_wraps_body",),);
+}
+
+#[mae_test]
+async fn no_span_by_default() {
+    must_eq(tracing::current_span(), None,);
+}
+
+#[mae_test]
+#[should_panic(expected = "boom")]
+async fn should_panic_passthrough() {
+    panic!("boom");
+}
+
+// the teardown panic must not replace the body's, or `expected` would fail to match
+#[mae_test(teardown = common::context::failing_teardown)]
+#[should_panic(expected = "boom")]
+async fn should_panic_survives_teardown_panic() {
+    panic!("boom");
+}
+
+#[mae_test(fresh_db)]
+#[ignore = "needs MAE_TEST_ADMIN_URL pointing at a Postgres superuser"]
+async fn fresh_db_binds_database_name() {
+    must_be_true(fresh_db.starts_with("mae_",),);
+    must_be_true(fresh_db.ends_with("_fresh_db_binds_database_name",),);
+}
+
+#[mae_test(seed = 12345)]
+async fn seed_is_reproducible() {
+    must_eq(rng.next_u64(), StdRng::seed_from_u64(12345,).next_u64(),);
+}
+
+#[mae_test(seed)]
+async fn seed_from_entropy() {
+    let _ = rng.next_u64();
+}
+
+#[mae_test(log = "trace")]
+async fn log_level_override() {
+    must_eq(tracing::subscriber::default_level(), Some(tracing::Level::TRACE,),);
+}
+
+#[mae_test]
+async fn ambient_subscriber_by_default() {
+    must_eq(tracing::subscriber::default_level(), None,);
+}
+
+#[mae_test(teardown = [common::context::failing_teardown, common::context::recording_teardown])]
+#[ignore = "driven by every_teardown_runs_when_first_panics"]
+async fn with_failing_teardown() {}
+
+#[test]
+fn every_teardown_runs_when_first_panics() {
+    let result = std::panic::catch_unwind(with_failing_teardown,);
+
+    must_be_true(result.is_err(),);
+    must_eq(common::context::RECORDED_TEARDOWNS.load(std::sync::atomic::Ordering::SeqCst,), 1,);
+}
+
+#[ignore = "needs external service"]
+#[mae_test]
+async fn ignore_reason_above_mae_test_is_kept() {
+    panic!("ignored tests must not run");
+}
+
+#[mae_test(keep_on_fail, teardown = common::context::keep_on_fail_teardown)]
+#[ignore = "driven by keep_on_fail_skips_teardown_only_on_failure"]
+async fn keep_on_fail_failing() {
+    panic!("leave my rows");
+}
+
+#[mae_test(keep_on_fail, teardown = common::context::keep_on_fail_teardown)]
+#[ignore = "driven by keep_on_fail_skips_teardown_only_on_failure"]
+async fn keep_on_fail_passing() {}
+
+#[test]
+fn keep_on_fail_skips_teardown_only_on_failure() {
+    use std::sync::atomic::Ordering;
+
+    must_be_true(std::panic::catch_unwind(keep_on_fail_failing,).is_err(),);
+    must_eq(common::context::KEEP_ON_FAIL_TEARDOWNS.load(Ordering::SeqCst,), 0,);
+
+    keep_on_fail_passing();
+    must_eq(common::context::KEEP_ON_FAIL_TEARDOWNS.load(Ordering::SeqCst,), 1,);
+}
+
+#[mae_test(metrics)]
+async fn metrics_recorded() {}
+
+#[mae_test(metrics)]
+#[should_panic(expected = "expected")]
+async fn metrics_recorded_for_should_panic() {
+    panic!("expected");
+}
+
+#[test]
+fn metrics_registry_collects_outcomes() {
+    metrics_recorded();
+    let _ = std::panic::catch_unwind(metrics_recorded_for_should_panic,);
+
+    let recorded = common::metrics::metrics();
+    must_be_true(recorded.iter().any(|m| m.name == "metrics_recorded" && m.passed,),);
+    must_be_true(
+        recorded.iter().any(|m| m.name == "metrics_recorded_for_should_panic" && m.passed,),
+    );
+    common::metrics::print_metrics();
+}
+
+static CUSTOM_RECORDED: std::sync::Mutex<Vec<&'static str,>,> = std::sync::Mutex::new(Vec::new(),);
+
+fn record_custom(name: &'static str, _elapsed: std::time::Duration, passed: bool,) {
+    if let Ok(mut recorded,) = CUSTOM_RECORDED.lock()
+        && passed
+    {
+        recorded.push(name,);
+    }
+}
+
+#[mae_test(metrics = record_custom)]
+async fn metrics_custom_recorder() {}
+
+#[test]
+fn metrics_uses_given_recorder() {
+    metrics_custom_recorder();
+
+    let recorded = CUSTOM_RECORDED.lock().map(|r| r.clone(),).unwrap_or_default();
+    // libtest runs metrics_custom_recorder on its own too, so it may be in there twice
+    must_contains(&recorded, &"metrics_custom_recorder",);
+}
+
+struct Counters {
+    unwrap_count: u32,
+}
+
+#[mae_test]
+async fn lint_ignores_lookalike_names() {
+    let c = Counters { unwrap_count: 2, };
+    let note = "no assert!(x) or .unwrap() here";
+    must_eq(c.unwrap_count, 2,);
+    must_be_true(note.contains("assert!",),);
+}
+
+#[mae_test(setup)]
+async fn setup_value_is_bound() {
+    must_eq(setup, 42,);
+}
+
+#[mae_test(setup = common::context::named_setup)]
+async fn setup_path_value_is_bound() {
+    must_eq(setup, "named",);
+}
+
+#[mae_test]
+async fn setup_is_not_bound_by_default() {
+    let setup = "the test's own";
+    must_eq(setup, "the test's own",);
+}
+
+#[mae_test(setup)]
+#[ignore = "driven by setup_body_teardown_run_in_order"]
+async fn records_body_stage() {
+    common::context::record_stage("body",);
+}
+
+#[test]
+fn setup_body_teardown_run_in_order() {
+    common::context::STAGES.with(|s| s.borrow_mut().clear(),);
+    records_body_stage();
+
+    must_eq(common::context::STAGES.take(), vec!["setup", "body", "teardown"],);
+}
+
+#[test]
+fn setup_panic_skips_body_but_not_teardown() {
+    common::context::STAGES.with(|s| s.borrow_mut().clear(),);
+    common::context::SETUP_PANICS.set(true,);
+    let result = std::panic::catch_unwind(records_body_stage,);
+    common::context::SETUP_PANICS.set(false,);
+
+    must_be_true(result.is_err(),);
+    must_eq(common::context::STAGES.take(), vec!["teardown"],);
+}
+
+#[mae_test]
+#[ignore = "driven by setup_is_off_by_default"]
+async fn without_setup() {
+    common::context::record_stage("body",);
+}
+
+#[test]
+fn setup_is_off_by_default() {
+    common::context::STAGES.with(|s| s.borrow_mut().clear(),);
+    without_setup();
+
+    must_eq(common::context::STAGES.take(), vec!["body", "teardown"],);
+}
+
+#[mae_test]
+async fn multi_thread_by_default() {
+    must_eq(tokio::runtime::last_built(), Some(("multi_thread", None,),),);
+}
+
+#[mae_test(flavor = "current_thread")]
+async fn current_thread_flavor() {
+    must_eq(tokio::runtime::last_built(), Some(("current_thread", None,),),);
+}
+
+#[mae_test(flavor = "multi_thread", worker_threads = 2)]
+async fn multi_thread_with_workers() {
+    must_eq(tokio::runtime::last_built(), Some(("multi_thread", Some(2,),),),);
+}
+
+#[mae_test(timeout_ms = 50)]
+#[should_panic(expected = "#[mae_test] times_out timed out after 50 ms")]
+async fn times_out() {
+    tokio::time::sleep(std::time::Duration::from_secs(5,),).await;
+}
+
+#[mae_test(timeout_ms = 5000)]
+async fn finishes_within_timeout() {
+    tokio::time::sleep(std::time::Duration::from_millis(1,),).await;
+}
+
+#[mae_test(no_teardown)]
+#[ignore = "driven by no_teardown_skips_teardown"]
+async fn without_teardown() {
+    common::context::record_stage("body",);
+}
+
+#[test]
+fn no_teardown_skips_teardown() {
+    common::context::STAGES.with(|s| s.borrow_mut().clear(),);
+    without_teardown();
+
+    must_eq(common::context::STAGES.take(), vec!["body"],);
+}
+
+#[mae_test(no_teardown)]
+#[should_panic(expected = "left for inspection")]
+async fn no_teardown_still_propagates_panics() {
+    panic!("left for inspection");
+}
+
+#[mae_test(teardown = "common::context::teardown")]
+#[ignore = "driven by string_teardown_path_runs"]
+async fn with_string_teardown() {}
+
+#[test]
+fn string_teardown_path_runs() {
+    common::context::STAGES.with(|s| s.borrow_mut().clear(),);
+    with_string_teardown();
+
+    must_eq(common::context::STAGES.take(), vec!["teardown"],);
+}
+
+#[mae_test(catch)]
+#[ignore = "driven by catch_returns_panic_as_err"]
+async fn catches_panic() -> Result<(), String,> {
+    common::context::record_stage("body",);
+    panic!("case 3 failed");
+}
+
+#[test]
+fn catch_returns_panic_as_err() {
+    common::context::STAGES.with(|s| s.borrow_mut().clear(),);
+
+    must_eq(catches_panic(), Err("case 3 failed".to_string(),),);
+    must_eq(common::context::STAGES.take(), vec!["body", "teardown"],);
+}
+
+#[mae_test(catch)]
+async fn catch_passes_ok_through() -> Result<(), String,> {
+    Ok((),)
+}
+
+#[mae_test(local)]
+async fn local_runs_not_send_tasks() {
+    let shared = std::rc::Rc::new(std::cell::Cell::new(1,),);
+    let task_shared = std::rc::Rc::clone(&shared,);
+    let handle = tokio::task::spawn_local(async move {
+        task_shared.set(task_shared.get() + 1,);
+        task_shared.get()
+    },);
+
+    must_eq(must_be_ok(handle.await,), 2,);
+    must_eq(shared.get(), 2,);
+    must_eq(tokio::runtime::last_built(), Some(("current_thread", None,),),);
+}
+
+#[mae_test(local, teardown = common::context::local_teardown)]
+#[ignore = "driven by local_teardown_runs_in_local_set"]
+async fn with_local_teardown() {}
+
+#[test]
+fn local_teardown_runs_in_local_set() {
+    common::context::STAGES.with(|s| s.borrow_mut().clear(),);
+    with_local_teardown();
+
+    must_eq(common::context::STAGES.take(), vec!["local teardown"],);
+}
+
+#[mae_test(trace)]
+#[ignore = "driven by trace_captures_logs_of_failing_test"]
+async fn traced_failure() {
+    tracing::info!("loading fixture {}", 7);
+    panic!("fixture missing");
+}
+
+#[test]
+fn trace_captures_logs_of_failing_test() {
+    must_be_true(std::panic::catch_unwind(traced_failure,).is_err(),);
+
+    must_contains(&tracing_subscriber::test_output(), &"INFO loading fixture 7".to_string(),);
+}
@@ -0,0 +1,1294 @@
+#[allow(unused)]
+mod common;
+
+use common::{
+    mae,
+    must::*,
+    rand::{self, SeedableRng, rngs::StdRng},
+    sqlx::{self, Arguments},
+};
+
+// Mirrors the default columns injected by `#[schema]` (expanded as a whole in tests/schema.rs).
+#[derive(mae_macros::MaeRepo, Debug, Clone,)]
+#[table("accounts")]
+pub struct Account {
+    #[locked]
+    pub id: i32,
+    #[insert_only]
+    pub sys_client: i32,
+    pub status: mae::repo::default::DomainStatus,
+    pub name: String,
+    pub comment: Option<String,>,
+    pub tags: serde_json::Value,
+    pub sys_detail: serde_json::Value,
+    #[locked]
+    pub created_by: i32,
+    #[locked]
+    pub updated_by: i32,
+    #[locked]
+    pub created_at: chrono::DateTime<chrono::Utc,>,
+    #[locked]
+    pub updated_at: chrono::DateTime<chrono::Utc,>,
+}
+
+#[test]
+fn describe_default_fields() {
+    let described = Field::describe()
+        .iter()
+        .map(|c| (c.field.to_string(), c.sql_type, c.nullable, c.updatable,),)
+        .collect::<Vec<_,>>();
+
+    must_eq(
+        described,
+        vec![
+            ("id".to_string(), "INTEGER", false, false,),
+            ("sys_client".to_string(), "INTEGER", false, false,),
+            ("status".to_string(), "DomainStatus", false, true,),
+            ("name".to_string(), "TEXT", false, true,),
+            ("comment".to_string(), "TEXT", true, true,),
+            ("tags".to_string(), "JSONB", false, true,),
+            ("sys_detail".to_string(), "JSONB", false, true,),
+            ("created_by".to_string(), "INTEGER", false, false,),
+            ("updated_by".to_string(), "INTEGER", false, false,),
+            ("created_at".to_string(), "TIMESTAMPTZ", false, false,),
+            ("updated_at".to_string(), "TIMESTAMPTZ", false, false,),
+        ],
+    );
+}
+
+fn account(sys_client: i32,) -> Account {
+    Account {
+        id: 1,
+        sys_client,
+        status: mae::repo::default::DomainStatus::Active,
+        name: "acme".into(),
+        comment: None,
+        tags: serde_json::json!([]),
+        sys_detail: serde_json::json!({}),
+        created_by: 1,
+        updated_by: 1,
+        created_at: chrono::Utc::now(),
+        updated_at: chrono::Utc::now(),
+    }
+}
+
+#[test]
+fn tenant_guard() {
+    let row = account(7,);
+
+    must_be_true(row.in_tenant(7,),);
+    must_be_true(!row.in_tenant(8,),);
+    must_be_ok(row.assert_tenant(7,),);
+    must_eq(row.assert_tenant(8,), Err(TenantMismatch { expected: 8, actual: 7, },),);
+}
+
+#[test]
+fn touch_and_patch_numbers_placeholders() {
+    let (sql, args,) = Account::touch_and_patch(
+        vec![PatchField::name("renamed".into(),), PatchField::comment(Some("hi".into(),),)],
+        3,
+        42,
+    );
+
+    must_eq(
+        sql.as_str(),
+        "UPDATE accounts SET name = $1, comment = $2, updated_at = now(), updated_by = $3 \
+         WHERE id = $4",
+    );
+    must_eq(args.values.join(" ",), "\"renamed\" Some(\"hi\") 3 42".to_string(),);
+
+    let (sql, args,) = Account::touch_and_patch(vec![], 3, 42,);
+    must_eq(sql.as_str(), "UPDATE accounts SET updated_at = now(), updated_by = $1 WHERE id = $2",);
+    must_eq(args.values.join(" ",), "3 42".to_string(),);
+}
+
+pub mod palette {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq,)]
+    pub enum Color {
+        Red,
+        Blue,
+    }
+
+    impl sqlx::postgres::PgHasArrayType for Color {}
+
+    impl<'de,> serde::Deserialize<'de,> for Color {
+        fn deserialize<D: serde::Deserializer<'de,>,>(d: D,) -> Result<Self, D::Error,> {
+            match String::deserialize(d,)?.as_str() {
+                "Red" => Ok(Self::Red,),
+                "Blue" => Ok(Self::Blue,),
+                other => Err(serde::de::Error::unknown_variant(other, &["Red", "Blue",],),),
+            }
+        }
+    }
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    pub struct Palette {
+        pub colors: Vec<Color,>,
+        pub weights: Option<Vec<i32,>,>,
+    }
+}
+
+#[test]
+fn vec_of_custom_type_with_array_impl() {
+    let described =
+        palette::Field::describe().iter().map(|c| (c.sql_type, c.nullable,),).collect::<Vec<_,>>();
+
+    must_eq(described, vec![("Color[]", false,), ("INTEGER[]", true,)],);
+}
+
+#[test]
+fn columns_with_prefix_qualifies_every_column() {
+    must_eq(
+        Field::columns_with_prefix("o",),
+        "o.id, o.sys_client, o.status, o.name, o.comment, o.tags, o.sys_detail, o.created_by, \
+         o.updated_by, o.created_at, o.updated_at"
+            .to_string(),
+    );
+    must_eq(Field::status.prefixed("o",), "o.status".to_string(),);
+}
+
+#[test]
+fn field_keys_hash_sets() {
+    let sorted: std::collections::HashSet<Field,> =
+        [Field::name, Field::created_at, Field::name,].into_iter().collect();
+
+    must_eq(sorted.len(), 2,);
+    must_be_true(sorted.contains(&Field::created_at,),);
+    must_be_true(!sorted.contains(&Field::All,),);
+}
+
+// `json!` with nested values expands to `unwrap`, which the lint config forbids.
+fn json(s: &str,) -> serde_json::Value {
+    must_be_ok(serde_json::from_str(s,),)
+}
+
+#[test]
+fn json_pointer_accessors() {
+    let mut row = account(1,);
+    row.sys_detail = json(r#"{ "meta": { "source": "import" } }"#,);
+    row.tags = json(r#"["a", "b"]"#,);
+
+    must_eq(row.sys_detail_get("/meta/source",), Some(&json(r#""import""#,),),);
+    must_eq(row.sys_detail_get("/meta/missing",), None,);
+    must_eq(row.tags_get("/1",), Some(&json(r#""b""#,),),);
+}
+
+// the tenant needn't be a field of the same name, only what ContextColumns hands over
+struct Ctx {
+    tenant: i32,
+}
+
+impl ContextColumns for Ctx {
+    fn sys_client(&self,) -> i32 {
+        self.tenant
+    }
+}
+
+#[test]
+fn tenant_guard_from_context() {
+    let row = account(7,);
+
+    must_be_ok(row.validate_tenant_scoped(&Ctx { tenant: 7, },),);
+    must_eq(
+        row.validate_tenant_scoped(&Ctx { tenant: 9, },),
+        Err(TenantMismatch { expected: 9, actual: 7, },),
+    );
+}
+
+pub mod tag {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[table("tags")]
+    pub struct Tag {
+        #[locked]
+        pub id: i32,
+        #[unique]
+        pub slug: String,
+        pub label: String,
+    }
+}
+
+#[test]
+fn insert_conflict_ignore_targets_unique_column() {
+    let row = tag::InsertRow { slug: "a".into(), label: "A".into(), };
+    must_eq(
+        row.insert_conflict_ignore_sql(),
+        "INSERT INTO tags (slug, label) VALUES ($1, $2) ON CONFLICT (slug) DO NOTHING".to_string(),
+    );
+}
+
+pub mod keyed_default {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[table("keys")]
+    pub struct Key {
+        #[locked]
+        pub id: i32,
+        #[default("now()")]
+        pub issued_at: chrono::DateTime<chrono::Utc,>,
+        #[unique]
+        pub code: String,
+    }
+}
+
+#[test]
+fn insert_conflict_ignore_leaves_unset_default_unbound() {
+    use mae::repo::__private__::BindArgs;
+
+    let row = keyed_default::InsertRow { issued_at: None, code: "k1".into(), };
+    must_eq(
+        row.insert_conflict_ignore_sql(),
+        "INSERT INTO keys (issued_at, code) VALUES (now(), $1) ON CONFLICT (code) DO NOTHING"
+            .to_string(),
+    );
+    must_eq(row.bind_len(), 1,);
+}
+
+#[test]
+fn upsert_clause_updates_non_unique_columns() {
+    must_eq(
+        tag::InsertRow::upsert_clause(),
+        "ON CONFLICT (slug) DO UPDATE SET label = EXCLUDED.label".to_string(),
+    );
+}
+
+pub mod membership {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[table("memberships")]
+    pub struct Membership {
+        #[locked]
+        pub id: i32,
+        #[unique]
+        pub org_id: i32,
+        #[unique]
+        pub user_id: i32,
+        pub role: String,
+        #[locked]
+        pub created_at: chrono::DateTime<chrono::Utc,>,
+    }
+}
+
+pub mod tenant_keyed {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[table("api_keys")]
+    pub struct ApiKey {
+        #[locked]
+        pub id: i32,
+        #[insert_only]
+        pub sys_client: i32,
+        #[unique]
+        pub name: String,
+        pub secret: String,
+        #[insert_only]
+        pub created_by: i32,
+    }
+}
+
+#[test]
+fn upsert_clause_keeps_insert_only_columns() {
+    must_eq(
+        tenant_keyed::InsertRow::upsert_clause(),
+        "ON CONFLICT (name) DO UPDATE SET secret = EXCLUDED.secret".to_string(),
+    );
+}
+
+#[test]
+fn upsert_clause_uses_composite_target() {
+    must_eq(
+        membership::InsertRow::upsert_clause(),
+        "ON CONFLICT (org_id, user_id) DO UPDATE SET role = EXCLUDED.role".to_string(),
+    );
+}
+
+#[test]
+fn cover_all_fields_accepts_every_column() {
+    tag::Field::must_cover_all_fields(&[tag::Field::label, tag::Field::id, tag::Field::slug,],);
+    tag::Field::must_cover_all_fields(&[tag::Field::All,],);
+}
+
+#[test]
+#[should_panic(expected = "fields not covered: label")]
+fn cover_all_fields_names_missing_column() {
+    tag::Field::must_cover_all_fields(&[tag::Field::id, tag::Field::slug,],);
+}
+
+#[test]
+fn status_check_and_predicate() {
+    use mae::repo::default::DomainStatus;
+
+    let row = account(1,);
+    must_be_true(row.status_is(DomainStatus::Active,),);
+    must_be_true(!row.status_is(DomainStatus::Inactive,),);
+
+    let (sql, args,) = Account::status_predicate(DomainStatus::Inactive,);
+    must_eq(sql.as_str(), "status = $1",);
+    must_eq(args.values, vec!["Inactive".to_string()],);
+}
+
+pub mod account_table {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[table("accounts")]
+    pub struct Account {
+        #[locked]
+        pub id: i32,
+        pub status: mae::repo::default::DomainStatus,
+        pub name: String,
+    }
+}
+
+#[test]
+fn select_by_filter() {
+    use account_table::{Account, Filter, Op};
+    use mae::repo::{__private__::BindArgs, default::DomainStatus};
+
+    let filter = Filter::status(Op::Eq, DomainStatus::Active,);
+    must_eq(Account::TABLE, "accounts",);
+    must_eq(
+        Account::select_by(&filter,),
+        "SELECT id, status, name FROM accounts WHERE status = $1".to_string(),
+    );
+
+    let mut args = sqlx::postgres::PgArguments::default();
+    filter.bind(&mut args,);
+    must_eq(args.values, vec!["Active".to_string()],);
+}
+
+#[test]
+fn as_patch_vec_covers_patchable_columns() {
+    let patches = account(1,).as_patch_vec();
+
+    must_eq(patches.len(), 5,);
+    must_eq(
+        patches.iter().map(|p| p.to_string(),).collect::<Vec<_,>>().join(", ",),
+        "status, name, comment, tags, sys_detail".to_string(),
+    );
+}
+
+#[test]
+fn reset_audit_clears_locked_columns() {
+    let mut row = account(1,);
+    row.id = 99;
+    row.created_by = 5;
+    let created_at = row.created_at;
+
+    row.reset_audit();
+
+    must_eq(row.id, 0,);
+    must_eq(row.created_by, 0,);
+    must_eq(row.updated_by, 0,);
+    must_be_true(row.created_at >= created_at,);
+    must_eq(row.sys_client, 1,);
+}
+
+#[test]
+fn columns_except_json_drops_json_columns() {
+    must_eq(
+        Field::columns_except_json(),
+        "id, sys_client, status, name, comment, created_by, updated_by, created_at, updated_at"
+            .to_string(),
+    );
+}
+
+pub mod derived_rows {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    // PartialEq is always derived, so only Eq and Hash are forwarded
+    #[row_derive(PartialEq, Eq, Hash)]
+    pub struct Note {
+        #[locked]
+        pub id: i32,
+        pub body: String,
+    }
+}
+
+#[test]
+fn row_debug_names_columns() {
+    let insert = renamed::InsertRow { kind: "admin".into(), name: "ann".into(), };
+    must_contains(&format!("{:?}", insert), "$1 (user_type) = \"admin\"",);
+    must_contains(&format!("{:?}", insert), "$2 (name) = \"ann\"",);
+
+    let update = tag::UpdateRow { slug: None, label: Some("b".into(),), };
+    must_contains(&format!("{:?}", update), "$1 (label) = \"b\"",);
+}
+
+#[test]
+fn rows_compare_without_row_derive() {
+    use chrono::Utc;
+
+    let row = || defaulted::InsertRow { label: "a".into(), seen_at: None, };
+    must_eq(row(), row(),);
+    must_ne(row(), defaulted::InsertRow { label: "a".into(), seen_at: Some(Utc::now(),), },);
+    must_eq(
+        defaulted::UpdateRow { label: None, seen_at: None, },
+        defaulted::UpdateRow { label: None, seen_at: None, },
+    );
+}
+
+#[test]
+fn row_derive_forwards_onto_rows() {
+    use derived_rows::{InsertRow, UpdateRow};
+    use std::collections::HashSet;
+
+    let inserts =
+        HashSet::from([InsertRow { body: "a".into(), }, InsertRow { body: "a".into(), },],);
+    must_eq(inserts.len(), 1,);
+    let updates =
+        HashSet::from([UpdateRow { body: Some("a".into(),), }, UpdateRow { body: None, },],);
+    must_eq(updates.len(), 2,);
+}
+
+#[test]
+fn bind_consistency_of_generated_types() {
+    let update = UpdateRow {
+        status: None,
+        name: Some("renamed".into(),),
+        comment: Some(None,),
+        tags: None,
+        sys_detail: None,
+    };
+    update.assert_bind_consistency();
+    must_be_true(update.bind_count_matches(2,),);
+
+    let insert = derived_rows::InsertRow { body: "hello".into(), };
+    insert.assert_bind_consistency();
+
+    PatchField::name("renamed".into(),).assert_bind_consistency();
+    Filter::id(Op::Gt, 3,).assert_bind_consistency();
+}
+
+#[test]
+fn pk_in_clause_numbers_from_start() {
+    must_eq(Account::pk_in_clause(&[4, 5, 6,], 2,), "id IN ($2, $3, $4)".to_string(),);
+    must_eq(Account::pk_in_clause(&[], 1,), "FALSE".to_string(),);
+
+    let mut args = sqlx::postgres::PgArguments::default();
+    Account::bind_pk_in(&[4, 5, 6,], &mut args,);
+    must_eq(args.values.join(", ",), "4, 5, 6".to_string(),);
+}
+
+#[test]
+fn filter_tree_renders_nested_groups() {
+    use mae::repo::__private__::{BindArgs, ToSqlParts};
+
+    let tree = Filter::id(Op::Eq, 1,)
+        .and(Filter::name(Op::Like, "a%".into(),).or(Filter::sys_client(Op::Gt, 2,),),);
+
+    let (sql, placeholders,) = tree.to_sql_parts();
+    must_eq(sql, vec!["(id = $1 AND (name LIKE $2 OR sys_client > $3))".to_string()],);
+    must_eq(placeholders.map(|p| p.len(),), Some(3,),);
+
+    let mut args = sqlx::postgres::PgArguments::default();
+    tree.bind(&mut args,);
+    must_eq(args.values.join(", ",), "1, \"a%\", 2".to_string(),);
+    must_eq(tree.bind_len(), 3,);
+}
+
+#[test]
+fn sql_create_table_from_fields() {
+    must_eq(
+        account_table::Account::sql_create_table(),
+        "CREATE TABLE accounts (id SERIAL PRIMARY KEY, status DomainStatus NOT NULL, name TEXT \
+         NOT NULL)"
+            .to_string(),
+    );
+}
+
+pub mod scoped {
+    use super::*;
+
+    pub struct RequestCtx {
+        pub tenant: i32,
+        pub user: i32,
+    }
+
+    impl ContextColumns for RequestCtx {
+        fn sys_client(&self,) -> i32 {
+            self.tenant
+        }
+
+        fn created_by(&self,) -> i32 {
+            self.user
+        }
+    }
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[context(RequestCtx)]
+    #[table("scoped")]
+    pub struct Scoped {
+        #[locked]
+        pub id: i32,
+        #[from_context]
+        pub sys_client: i32,
+        #[locked]
+        #[from_context]
+        pub created_by: i32,
+        pub label: String,
+    }
+}
+
+#[test]
+fn writable_fields_skip_context_columns() {
+    must_eq(scoped::Scoped::writable_fields(), vec!["label"],);
+}
+
+#[test]
+fn from_context_fields_leave_the_rows() {
+    use mae::repo::__private__::{BindArgs, ToSqlParts};
+
+    let ctx = scoped::ContextRow::from_context(&scoped::RequestCtx { tenant: 3, user: 11, },);
+    must_eq(ctx.sys_client, 3,);
+    must_eq(ctx.created_by, 11,);
+    must_eq(ctx.to_sql_parts().0.join(", ",), "sys_client, created_by".to_string(),);
+    must_eq(ctx.bind_len(), 2,);
+
+    let insert = scoped::InsertRow { label: "a".into(), };
+    must_eq(insert.to_sql_parts().0.join(", ",), "label".to_string(),);
+    let update = scoped::UpdateRow { label: None, };
+    must_eq(update.bind_len(), 0,);
+    must_eq(scoped::PatchField::label("b".into(),).to_string(), "label".to_string(),);
+}
+
+#[test]
+fn insert_sql_appends_context_columns() {
+    let ctx = scoped::ContextRow::from_context(&scoped::RequestCtx { tenant: 3, user: 11, },);
+    let insert = scoped::InsertRow { label: "a".into(), };
+
+    must_eq(
+        insert.insert_sql(&ctx,),
+        "INSERT INTO scoped (label, sys_client, created_by) VALUES ($1, $2, $3)".to_string(),
+    );
+}
+
+#[test]
+fn patch_field_converts_to_field() {
+    let patch = PatchField::name("acme".into(),);
+    must_eq(Field::from(&patch,).to_string(), "name".to_string(),);
+
+    let fields = account(1,)
+        .as_patch_vec()
+        .iter()
+        .map(|p| Field::from(p,).to_string(),)
+        .collect::<Vec<_,>>();
+    must_eq(fields.join(", ",), "status, name, comment, tags, sys_detail".to_string(),);
+}
+
+#[test]
+fn field_iter_yields_each_column_once() {
+    let names = Field::iter().map(|f| f.to_string(),).collect::<Vec<_,>>();
+    must_eq(names.len(), 11,);
+
+    let distinct = names.iter().collect::<std::collections::HashSet<_,>>();
+    must_eq(distinct.len(), names.len(),);
+    must_be_true(!names.iter().any(|n| n.contains(", ",),),);
+}
+
+pub mod random {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[arbitrary]
+    pub struct Sample {
+        #[locked]
+        pub id: i32,
+        pub label: String,
+        pub note: Option<String,>,
+        pub detail: serde_json::Value,
+        pub seen_at: chrono::DateTime<chrono::Utc,>,
+        pub status: mae::repo::default::DomainStatus,
+        pub rank: core::primitive::i64,
+    }
+}
+
+#[test]
+fn arbitrary_rows_round_trip_through_insert_row() {
+    use mae::repo::__private__::BindArgs;
+
+    let row = random::Sample::arbitrary(&mut StdRng::seed_from_u64(42,),);
+    must_be_true(row.id >= 0,);
+    must_be_true(row.label.starts_with("label_",),);
+    must_be_true(row.rank >= 0,);
+
+    let insert = random::InsertRow {
+        label: row.label.clone(),
+        note: row.note.clone(),
+        detail: row.detail.clone(),
+        seen_at: row.seen_at,
+        status: row.status,
+        rank: row.rank,
+    };
+    insert.assert_bind_consistency();
+    must_eq(insert.bind_len(), 6,);
+
+    must_eq(random::Sample::arbitrary(&mut StdRng::seed_from_u64(42,),).label, row.label,);
+}
+
+#[test]
+fn patch_field_placeholders_follow_start_index() {
+    use mae::repo::__private__::ToSqlParts;
+
+    let name = PatchField::name("acme".into(),);
+    let comment = PatchField::comment(None,);
+
+    must_eq(name.to_sql_parts().1, Some(vec!["$1".to_string()],),);
+
+    let ((cols, placeholders,), next,) = name.to_sql_parts_at(1,);
+    must_eq(cols, vec!["name".to_string()],);
+    must_eq(placeholders, Some(vec!["$1".to_string()],),);
+    let ((cols, placeholders,), next,) = comment.to_sql_parts_at(next,);
+    must_eq(cols, vec!["comment".to_string()],);
+    must_eq(placeholders, Some(vec!["$2".to_string()],),);
+    must_eq(next, 3,);
+}
+
+#[test]
+fn patch_set_numbers_and_binds_in_order() {
+    use mae::repo::__private__::BindArgs;
+
+    let set = PatchSet(vec![
+        PatchField::name("acme".into(),),
+        PatchField::comment(None,),
+        PatchField::status(mae::repo::default::DomainStatus::Active,),
+    ],);
+    must_eq(set.set_clause(1,), ("SET name = $1, comment = $2, status = $3".to_string(), 4,),);
+    must_eq(set.set_clause(5,).1, 8,);
+    must_eq(set.bind_len(), 3,);
+
+    must_eq(PatchSet(vec![],).set_clause(1,), (String::new(), 1,),);
+}
+
+#[test]
+fn from_json_entry_builds_typed_patch() {
+    let patch = must_be_ok(PatchField::from_json_entry("name", json(r#""acme""#,),),);
+    must_eq(format!("{:?}", patch), r#""acme""#.to_string(),);
+
+    let patch = must_be_ok(PatchField::from_json_entry("status", json(r#""Inactive""#,),),);
+    must_eq(format!("{:?}", patch), "Inactive".to_string(),);
+}
+
+#[test]
+fn from_json_entry_rejects_mismatched_value() {
+    let err = must_be_err(PatchField::from_json_entry("name", json("7",),),);
+
+    must_be_true(matches!(&err, PatchError::InvalidValue { column, .. } if column == "name"),);
+    must_be_true(err.to_string().starts_with("invalid value for patch column `name`: ",),);
+}
+
+#[test]
+fn from_json_entry_names_unknown_column() {
+    let err = must_be_err(PatchField::from_json_entry("nickname", json("1",),),);
+
+    must_eq(err.to_string(), "unknown patch column `nickname`".to_string(),);
+    // locked columns aren't patchable, so they're unknown here too
+    must_be_true(matches!(
+        PatchField::from_json_entry("id", json("1",),),
+        Err(PatchError::UnknownColumn(c)) if c == "id"
+    ),);
+}
+
+#[test]
+fn patch_set_from_map_follows_column_order() {
+    let entries = std::collections::HashMap::from([
+        ("status".to_string(), json(r#""Active""#,),),
+        ("name".to_string(), json(r#""acme""#,),),
+    ],);
+    let set = must_be_ok(PatchSet::try_from(entries,),);
+    must_eq(set.set_clause(1,).0, "SET status = $1, name = $2".to_string(),);
+
+    let entries = std::collections::HashMap::from([("nope".to_string(), json("1",),),],);
+    must_be_true(
+        matches!(PatchSet::try_from(entries), Err(PatchError::UnknownColumn(c)) if c == "nope"),
+    );
+}
+
+#[test]
+fn apply_writes_patch_onto_row() {
+    let mut note = derived_rows::Note { id: 4, body: "draft".into(), };
+    note.apply(&derived_rows::PatchField::body("final".into(),),);
+
+    must_eq(note.body, "final".to_string(),);
+    must_eq(note.id, 4,);
+}
+
+pub mod renamed {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[table("users")]
+    pub struct User {
+        #[locked]
+        pub id: i32,
+        #[column("user_type")]
+        pub kind: String,
+        pub name: String,
+    }
+}
+
+pub mod renamed_pk {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    pub struct Member {
+        #[locked]
+        #[column("member_id")]
+        pub id: i32,
+        pub name: String,
+    }
+}
+
+#[test]
+fn pk_in_clause_uses_the_id_column_name() {
+    must_eq(renamed_pk::Member::pk_in_clause(&[1, 2,], 3,), "member_id IN ($3, $4)".to_string(),);
+}
+
+#[test]
+fn column_attr_renames_sql_only() {
+    use mae::repo::__private__::ToSqlParts;
+
+    must_eq(renamed::Field::kind.to_string(), "user_type".to_string(),);
+    must_eq(renamed::Field::All.to_string(), "id, user_type, name".to_string(),);
+    must_eq(renamed::PatchField::kind("admin".into(),).to_string(), "user_type".to_string(),);
+
+    let insert = renamed::InsertRow { kind: "admin".into(), name: "ann".into(), };
+    must_eq(insert.to_sql_parts().0.join(", ",), "user_type, name".to_string(),);
+    must_eq(
+        renamed::User::sql_create_table(),
+        "CREATE TABLE users (id SERIAL PRIMARY KEY, user_type TEXT NOT NULL, name TEXT NOT NULL)"
+            .to_string(),
+    );
+}
+
+pub mod uuid_keyed {
+    use super::*;
+
+    // like uuid::Uuid, Default is the nil id
+    #[derive(Debug, Clone, Copy, PartialEq, Default,)]
+    pub struct Uuid(pub u128,);
+
+    // Mirrors `#[schema(Ctx, "documents", id = uuid::Uuid)]`.
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[table("documents")]
+    pub struct Document {
+        #[locked]
+        pub id: Uuid,
+        pub title: String,
+        #[locked]
+        pub updated_by: i32,
+        #[locked]
+        pub updated_at: chrono::DateTime<chrono::Utc,>,
+    }
+}
+
+#[test]
+fn uuid_primary_key_stays_out_of_insert_row() {
+    use mae::repo::__private__::ToSqlParts;
+
+    let insert = uuid_keyed::InsertRow { title: "spec".into(), };
+    must_eq(insert.to_sql_parts().0.join(", ",), "title".to_string(),);
+    must_eq(
+        uuid_keyed::Document::pk_in_clause(&[uuid_keyed::Uuid(1,),], 1,),
+        "id IN ($1)".to_string(),
+    );
+
+    let (sql, args,) = uuid_keyed::Document::touch_and_patch(
+        vec![uuid_keyed::PatchField::title("v2".into(),)],
+        4,
+        uuid_keyed::Uuid(9,),
+    );
+    must_eq(
+        sql,
+        "UPDATE documents SET title = $1, updated_at = now(), updated_by = $2 WHERE id = $3"
+            .to_string(),
+    );
+    must_eq(args.values.last().cloned(), Some("Uuid(9)".to_string(),),);
+}
+
+pub mod revisioned {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    pub struct Page {
+        #[locked]
+        pub id: i32,
+        pub title: String,
+        #[update_only]
+        pub revision: i32,
+    }
+}
+
+#[test]
+fn update_only_fields_always_bind_on_update() {
+    use mae::repo::__private__::{BindArgs, ToSqlParts};
+
+    let insert = revisioned::InsertRow { title: "a".into(), };
+    must_eq(insert.to_sql_parts().0, vec!["title".to_string()],);
+
+    let bare = revisioned::UpdateRow { title: None, revision: 2, };
+    must_eq(bare.bind_len(), 1,);
+    must_eq(bare.to_sql_parts(), (vec!["revision".to_string()], Some(vec!["$1".to_string()],),),);
+
+    let full = revisioned::UpdateRow { title: Some("b".into(),), revision: 3, };
+    must_eq(full.bind_len(), 2,);
+    full.assert_bind_consistency();
+}
+
+#[test]
+fn table_const_needs_no_build_trait() {
+    let sql = format!("DELETE FROM {} WHERE id = $1", tag::Tag::TABLE);
+    must_eq(sql, "DELETE FROM tags WHERE id = $1".to_string(),);
+}
+
+#[test]
+fn where_builder_numbers_conditions() {
+    use tag::{Field, WhereBuilder};
+
+    must_eq(Field::eq(Field::slug, 3,), "slug = $3".to_string(),);
+    // method calls still reach PartialEq
+    must_be_true(Field::slug.eq(&Field::slug,),);
+    must_eq(
+        WhereBuilder::new().eq(Field::slug,).eq(Field::label,).render(),
+        "WHERE slug = $1 AND label = $2".to_string(),
+    );
+
+    let after_set = WhereBuilder::starting_at(3,).eq(Field::id,);
+    must_eq(after_set.render(), "WHERE id = $3".to_string(),);
+    must_eq(after_set.next_bind_idx(), 4,);
+    must_eq(WhereBuilder::new().render(), String::new(),);
+}
+
+#[test]
+fn select_sql_lists_requested_columns() {
+    use tag::{Field, Tag};
+
+    must_eq(
+        Tag::select_sql(&[Field::slug, Field::label,],),
+        "SELECT slug, label FROM tags".to_string(),
+    );
+    must_eq(Tag::select_sql(&[Field::All,],), "SELECT id, slug, label FROM tags".to_string(),);
+    must_eq(Tag::select_sql(&[],), Tag::select_sql(&[Field::All,],),);
+}
+
+#[test]
+fn returning_clause_lists_every_column() {
+    use tag::{Field, Tag};
+
+    must_eq(Tag::returning_clause(), format!("RETURNING {}", Field::All),);
+    must_eq(Tag::returning_clause(), "RETURNING id, slug, label".to_string(),);
+    must_eq(Tag::returning(&[Field::id,],), "RETURNING id".to_string(),);
+}
+
+pub mod stamped {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[table("stamps")]
+    pub struct Stamp {
+        #[locked]
+        pub id: i32,
+        pub label: String,
+        #[insert_only]
+        #[default("now()")]
+        pub created_marker: chrono::DateTime<chrono::Utc,>,
+    }
+}
+
+#[test]
+fn insert_only_default_is_inserted_but_never_updated() {
+    use mae::repo::__private__::{BindArgs, ToSqlParts};
+
+    let insert = stamped::InsertRow { label: "a".into(), created_marker: None, };
+    must_eq(
+        insert.to_sql_parts(),
+        (
+            vec!["label".to_string(), "created_marker".to_string()],
+            Some(vec!["$1".to_string(), "now()".to_string()],),
+        ),
+    );
+    must_eq(insert.bind_len(), 1,);
+
+    // UpdateRow has no created_marker at all
+    let update = stamped::UpdateRow { label: Some("b".into(),), };
+    must_eq(update.to_sql_parts().0, vec!["label".to_string()],);
+    must_be_true(
+        stamped::Stamp::sql_create_table()
+            .ends_with("created_marker TIMESTAMPTZ NOT NULL DEFAULT now())",),
+    );
+}
+
+#[test]
+fn insert_sql_numbers_only_bound_columns() {
+    let unset = defaulted::InsertRow { label: "a".into(), seen_at: None, };
+    must_eq(
+        unset.insert_sql(),
+        "INSERT INTO events (label, seen_at) VALUES ($1, now())".to_string(),
+    );
+
+    let set = defaulted::InsertRow { label: "a".into(), seen_at: Some(chrono::Utc::now(),), };
+    must_eq(set.insert_sql(), "INSERT INTO events (label, seen_at) VALUES ($1, $2)".to_string(),);
+
+    let tag = tag::InsertRow { slug: "s".into(), label: "l".into(), };
+    must_eq(tag.insert_sql(), "INSERT INTO tags (slug, label) VALUES ($1, $2)".to_string(),);
+}
+
+pub mod defaulted {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[table("events")]
+    pub struct Event {
+        #[locked]
+        pub id: i32,
+        pub label: String,
+        #[default("now()")]
+        pub seen_at: chrono::DateTime<chrono::Utc,>,
+    }
+}
+
+#[test]
+fn default_attr_writes_sql_expression_when_unset() {
+    use mae::repo::__private__::{BindArgs, ToSqlParts};
+
+    let unset = defaulted::InsertRow { label: "a".into(), seen_at: None, };
+    must_eq(
+        unset.to_sql_parts(),
+        (
+            vec!["label".to_string(), "seen_at".to_string()],
+            Some(vec!["$1".to_string(), "now()".to_string()],),
+        ),
+    );
+    must_eq(unset.bind_len(), 1,);
+    unset.assert_bind_consistency();
+
+    let set = defaulted::InsertRow { label: "a".into(), seen_at: Some(chrono::Utc::now(),), };
+    must_eq(set.to_sql_parts().1, Some(vec!["$1".to_string(), "$2".to_string()],),);
+    must_eq(set.bind_len(), 2,);
+
+    must_eq(
+        defaulted::Event::sql_create_table(),
+        "CREATE TABLE events (id SERIAL PRIMARY KEY, label TEXT NOT NULL, seen_at TIMESTAMPTZ NOT \
+         NULL DEFAULT now())"
+            .to_string(),
+    );
+}
+
+#[test]
+fn fields_const_lists_columns() {
+    must_eq(Account::FIELDS.len(), 11,);
+    must_eq(Account::FIELDS.join(", ",), Field::All.to_string(),);
+    must_eq(renamed::User::FIELDS, &["id", "user_type", "name",][..],);
+}
+
+pub mod with_skip {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[arbitrary]
+    pub struct Order {
+        #[locked]
+        pub id: i32,
+        pub total: i64,
+        #[skip]
+        pub customer_name: String,
+    }
+}
+
+#[test]
+fn skipped_fields_are_not_columns() {
+    use mae::repo::__private__::ToSqlParts;
+
+    must_eq(with_skip::Field::All.to_string(), "id, total".to_string(),);
+    must_eq(with_skip::Order::FIELDS, &["id", "total",][..],);
+    must_eq(with_skip::Field::iter().count(), 2,);
+
+    // constructing these with only the non-skipped fields is the real check
+    let insert = with_skip::InsertRow { total: 5, };
+    must_eq(insert.to_sql_parts().0, vec!["total".to_string()],);
+    let update = with_skip::UpdateRow { total: None, };
+    must_eq(update.to_sql_parts().0.len(), 0,);
+    must_eq(with_skip::PatchField::total(1,).to_string(), "total".to_string(),);
+
+    must_eq(
+        with_skip::Order::arbitrary(&mut StdRng::seed_from_u64(1,),).customer_name,
+        String::new(),
+    );
+}
+
+pub mod with_marker {
+    use super::*;
+
+    #[derive(Debug, Clone, Default,)]
+    pub struct Draft;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[arbitrary]
+    pub struct Quote {
+        #[locked]
+        pub id: i32,
+        pub total: i64,
+        pub _marker: std::marker::PhantomData<Draft,>,
+    }
+}
+
+#[test]
+fn phantom_fields_are_not_columns() {
+    use mae::repo::__private__::ToSqlParts;
+
+    must_eq(with_marker::Field::All.to_string(), "id, total".to_string(),);
+    must_eq(with_marker::Quote::FIELDS, &["id", "total",][..],);
+
+    let insert = with_marker::InsertRow { total: 5, };
+    must_eq(insert.to_sql_parts().0, vec!["total".to_string()],);
+    let update = with_marker::UpdateRow { total: None, };
+    must_eq(update.to_sql_parts().0.len(), 0,);
+    must_eq(with_marker::PatchField::total(1,).to_string(), "total".to_string(),);
+
+    must_eq(
+        with_marker::Quote::arbitrary(&mut StdRng::seed_from_u64(1,),)._marker,
+        std::marker::PhantomData,
+    );
+}
+
+#[test]
+fn insert_row_builder_matches_literal() {
+    use derived_rows::InsertRow;
+
+    let built = must_be_ok(InsertRow::builder().body("hi".into(),).build(),);
+    must_eq(built, InsertRow { body: "hi".into(), },);
+
+    must_eq(InsertRow::builder().build().map(|_| (),), Err("missing `body` in InsertRow",),);
+}
+
+#[test]
+fn insert_row_builder_leaves_defaults_optional() {
+    let built = must_be_ok(defaulted::InsertRow::builder().label("a".into(),).build(),);
+    must_eq(built.label, "a".to_string(),);
+    must_be_true(built.seen_at.is_none(),);
+}
+
+pub mod nullable {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[table("contacts")]
+    pub struct Contact {
+        #[locked]
+        pub id: i32,
+        pub name: String,
+        #[nullable]
+        pub phone: Option<String,>,
+    }
+}
+
+#[test]
+fn nullable_column_inserts_none_as_null() {
+    use mae::repo::__private__::{BindArgs, ToSqlParts};
+
+    let row = nullable::InsertRow { name: "a".into(), phone: None, };
+    must_eq(
+        row.to_sql_parts(),
+        (
+            vec!["name".to_string(), "phone".to_string()],
+            Some(vec!["$1".to_string(), "$2".to_string()],),
+        ),
+    );
+    must_eq(row.bind_len(), 2,);
+    row.assert_bind_consistency();
+    must_be_true(format!("{:?}", row).contains("$2 (phone) = NULL",),);
+
+    let built = must_be_ok(nullable::InsertRow::builder().name("a".into(),).build(),);
+    must_be_true(built.phone.is_none(),);
+    must_be_true(nullable::Contact::sql_create_table().ends_with("phone TEXT)",),);
+
+    let phone =
+        nullable::Field::describe().iter().find(|c| c.field.to_string() == "phone",).cloned();
+    must_be_true(phone.is_some_and(|c| c.nullable,),);
+    let clear = nullable::PatchField::phone(None,);
+    must_eq(nullable::Field::from(&clear,).to_string(), "phone".to_string(),);
+}
+
+pub mod mixed_access {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    pub struct Invoice {
+        #[locked]
+        pub id: i32,
+        pub total: i64,
+        #[insert_only]
+        pub issued_to: String,
+        #[update_only]
+        pub revision: i32,
+        #[readonly]
+        pub created_at: chrono::DateTime<chrono::Utc,>,
+        pub memo: Option<String,>,
+    }
+}
+
+#[test]
+fn selectable_and_writable_fields_partition_columns() {
+    use mixed_access::Invoice;
+
+    must_eq(Invoice::selectable_fields(), Invoice::FIELDS.to_vec(),);
+    must_eq(Invoice::writable_fields(), vec!["total", "memo"],);
+
+    let read_only = Invoice::selectable_fields()
+        .into_iter()
+        .filter(|c| !Invoice::writable_fields().contains(c,),)
+        .collect::<Vec<_,>>();
+    must_eq(read_only, vec!["id", "issued_to", "revision", "created_at"],);
+}
+
+#[test]
+fn describe_mixed_attributes() {
+    let described = mixed_access::Field::describe()
+        .iter()
+        .map(|c| (c.field.to_string(), c.rust_type, c.updatable, c.nullable,),)
+        .collect::<Vec<_,>>();
+    let meta = |name: &str, rust_type, updatable, nullable| {
+        (name.to_string(), rust_type, updatable, nullable,)
+    };
+    must_eq(
+        described,
+        vec![
+            meta("id", "i32", false, false,),
+            meta("total", "i64", true, false,),
+            meta("issued_to", "String", false, false,),
+            meta("revision", "i32", true, false,),
+            // the type is as written in the struct, trailing comma included
+            meta("created_at", "chrono::DateTime<chrono::Utc,>", false, false,),
+            meta("memo", "Option<String,>", true, true,),
+        ],
+    );
+}
+
+#[test]
+fn readonly_is_kept_out_of_rows_like_locked() {
+    let insert = mixed_access::InsertRow { total: 1, issued_to: "a".into(), memo: None, };
+    let update = mixed_access::UpdateRow { total: None, revision: 2, memo: None, };
+    must_eq(insert.clone(), insert,);
+    must_eq(update.clone(), update,);
+    must_be_true(
+        !mixed_access::Field::describe()
+            .iter()
+            .any(|c| c.field.to_string() == "created_at" && c.updatable,),
+    );
+}
+
+#[test]
+fn fully_populated_rows_bind_every_placeholder() {
+    defaulted::InsertRow::__assert_bind_consistency();
+    nullable::InsertRow::__assert_bind_consistency();
+    mixed_access::UpdateRow::__assert_bind_consistency();
+}
+
+// A column type without `Default` leaves the rows without `__assert_bind_consistency` instead of
+// failing the test build.
+pub mod undefaulted {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq,)]
+    pub enum Mood {
+        Calm,
+        Busy,
+    }
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    pub struct Visitor {
+        #[locked]
+        pub id: i32,
+        #[insert_only]
+        pub mood: Mood,
+        pub name: String,
+    }
+}
+
+#[test]
+fn rows_without_default_columns_still_build() {
+    let insert = undefaulted::InsertRow { mood: undefaulted::Mood::Busy, name: "ann".into(), };
+    must_eq(insert.mood.clone(), undefaulted::Mood::Busy,);
+    must_eq(insert.clone(), insert,);
+}
+
+pub mod validated {
+    use super::*;
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[validate(with = "rules::positive_amount")]
+    pub struct Payment {
+        #[locked]
+        pub id: i32,
+        pub amount: i64,
+    }
+
+    pub mod rules {
+        use super::{InsertRow, ValidationError};
+
+        pub fn positive_amount(row: &InsertRow,) -> Result<(), ValidationError,> {
+            match row.amount > 0 {
+                true => Ok((),),
+                false => Err(ValidationError { message: "amount must be positive".into(), },),
+            }
+        }
+    }
+}
+
+#[test]
+fn validate_hook_rejects_row() {
+    must_be_ok(validated::InsertRow { amount: 5, }.validate(),);
+
+    let err = must_be_err(validated::InsertRow { amount: -1, }.validate(),);
+    must_eq(err.to_string(), "amount must be positive".to_string(),);
+}
+
+pub mod prioritized {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Default,)]
+    pub enum Priority {
+        #[default]
+        Low,
+        High,
+    }
+
+    impl<'de,> serde::Deserialize<'de,> for Priority {
+        fn deserialize<D: serde::Deserializer<'de,>,>(d: D,) -> Result<Self, D::Error,> {
+            match String::deserialize(d,)?.as_str() {
+                "Low" => Ok(Self::Low,),
+                "High" => Ok(Self::High,),
+                other => Err(serde::de::Error::unknown_variant(other, &["Low", "High",],),),
+            }
+        }
+    }
+
+    #[derive(mae_macros::MaeRepo, Debug, Clone,)]
+    #[table("tickets")]
+    pub struct Ticket {
+        #[locked]
+        pub id: i32,
+        #[enum_column(variants("Low", "High"))]
+        pub priority: Priority,
+        pub title: String,
+    }
+}
+
+#[test]
+fn enum_column_lists_variants() {
+    must_eq(prioritized::Ticket::PRIORITY_VARIANTS, &["Low", "High",][..],);
+
+    // every listed name deserializes into the field's enum, so the list can feed patches
+    for variant in prioritized::Ticket::PRIORITY_VARIANTS {
+        let entry =
+            prioritized::PatchField::from_json_entry("priority", json(&format!("{:?}", variant),),);
+        must_be_true(entry.is_ok(),);
+    }
+}
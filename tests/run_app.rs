@@ -0,0 +1,210 @@
+#[allow(unused)]
+mod common;
+
+use common::{actix::*, must::*, tokio};
+use mae_macros::run_app;
+
+fn serve<F: Future<Output = Result<Server, anyhow::Error,>,>,>(run: F,) -> Vec<String,> {
+    match tokio::runtime::Builder::new_multi_thread().build() {
+        Ok(rt,) => match rt.block_on(run,) {
+            Ok(server,) => server.calls,
+            Err(e,) => panic!("run failed: {:?}", e),
+        },
+        Err(e,) => panic!("runtime failed: {:?}", e),
+    }
+}
+
+fn secret() -> SecretString {
+    SecretString("s".into(),)
+}
+
+const DEFAULT_STACK: [&str; 6] = [
+    "wrap(TracingLogger)",
+    "wrap(SessionMiddleware)",
+    "app_data(ApplicationBaseUrl)",
+    "app_data(HmacSecret)",
+    "app_data(PgPool)",
+    "app_data(u8)",
+];
+
+fn expected(extra: &[&str],) -> Vec<String,> {
+    DEFAULT_STACK.iter().chain(extra,).map(|c| c.to_string(),).collect()
+}
+
+mod single {
+    use super::*;
+
+    #[run_app]
+    fn routes() {
+        service("/health",)
+    }
+
+    #[test]
+    fn single_statement_extends_app() {
+        let calls = serve(run(TcpListener, PgPool, "http://x".into(), secret(), secret(), 0u8,),);
+        must_eq(calls, expected(&["service(/health)",],),);
+    }
+}
+
+mod multi {
+    use super::*;
+
+    fn cfg(calls: &mut Vec<String,>,) {
+        calls.push("configured".into(),);
+    }
+
+    #[run_app]
+    fn routes() {
+        service("/health",);
+        configure(cfg,);
+        service("/users",)
+    }
+
+    #[test]
+    fn every_statement_extends_app() {
+        let calls = serve(run(TcpListener, PgPool, "http://x".into(), secret(), secret(), 0u8,),);
+        must_eq(calls, expected(&["service(/health)", "configured", "service(/users)",],),);
+    }
+}
+
+fn with_middleware(middleware: &[&str], extra: &[&str],) -> Vec<String,> {
+    let mut calls = expected(extra,);
+    for (i, m,) in middleware.iter().enumerate() {
+        // after the tracing and session middleware
+        calls.insert(2 + i, m.to_string(),);
+    }
+    calls
+}
+
+mod cors {
+    use super::*;
+
+    #[run_app(cors)]
+    fn routes() {
+        service("/health",)
+    }
+
+    #[test]
+    fn cors_wraps_app() {
+        let calls = serve(run(TcpListener, PgPool, "http://x".into(), secret(), secret(), 0u8,),);
+        must_eq(calls, with_middleware(&["wrap(Cors)",], &["service(/health)",],),);
+    }
+}
+
+mod compress {
+    use super::*;
+
+    #[run_app(compress)]
+    fn routes() {
+        service("/health",)
+    }
+
+    #[test]
+    fn compress_wraps_app() {
+        let calls = serve(run(TcpListener, PgPool, "http://x".into(), secret(), secret(), 0u8,),);
+        must_eq(calls, with_middleware(&["wrap(Compress)",], &["service(/health)",],),);
+    }
+}
+
+mod cors_and_compress {
+    use super::*;
+
+    #[run_app(compress, cors)]
+    fn routes() {
+        service("/health",)
+    }
+
+    #[test]
+    fn both_wrap_app_in_fixed_order() {
+        let calls = serve(run(TcpListener, PgPool, "http://x".into(), secret(), secret(), 0u8,),);
+        must_eq(
+            calls,
+            with_middleware(&["wrap(Cors)", "wrap(Compress)",], &["service(/health)",],),
+        );
+    }
+}
+
+mod named {
+    use super::*;
+
+    #[run_app(name = build_admin_server)]
+    fn admin_routes() {
+        service("/admin",)
+    }
+
+    #[run_app]
+    fn public_routes() {
+        service("/",)
+    }
+
+    #[test]
+    fn two_servers_in_one_module() {
+        let admin = serve(build_admin_server(
+            TcpListener,
+            PgPool,
+            "http://x".into(),
+            secret(),
+            secret(),
+            0u8,
+        ),);
+        let public = serve(run(TcpListener, PgPool, "http://x".into(), secret(), secret(), 0u8,),);
+
+        must_eq(admin, expected(&["service(/admin)",],),);
+        must_eq(public, expected(&["service(/)",],),);
+    }
+}
+
+mod stateless {
+    use super::*;
+
+    #[run_app(no_session)]
+    fn routes() {
+        service("/health",)
+    }
+
+    #[test]
+    fn no_session_drops_redis_and_secret() {
+        // no hmac_secret / redis_uri parameters
+        let calls = serve(run(TcpListener, PgPool, "http://x".into(), 0u8,),);
+        must_eq(
+            calls,
+            [
+                "wrap(TracingLogger)",
+                "app_data(ApplicationBaseUrl)",
+                "app_data(PgPool)",
+                "app_data(u8)",
+                "service(/health)",
+            ]
+            .map(String::from,)
+            .to_vec(),
+        );
+    }
+}
+
+mod extra {
+    use super::*;
+
+    #[derive(Clone,)]
+    struct FeatureFlags;
+
+    #[run_app(extra)]
+    fn routes() {
+        service("/health",)
+    }
+
+    #[test]
+    fn extra_is_registered_after_context() {
+        let calls = serve(run(
+            TcpListener,
+            PgPool,
+            "http://x".into(),
+            secret(),
+            secret(),
+            0u8,
+            FeatureFlags,
+        ),);
+        let mut expected = expected(&["service(/health)",],);
+        expected.insert(DEFAULT_STACK.len(), "app_data(FeatureFlags)".into(),);
+        must_eq(calls, expected,);
+    }
+}
@@ -0,0 +1,94 @@
+#[allow(unused)]
+mod common;
+
+use common::{
+    mae::{self, repo::default::DomainStatus},
+    must::*,
+    serde,
+    sqlx::{self, Arguments},
+    tokio,
+};
+use mae_macros::{mae_test, schema};
+
+#[derive(Debug, Clone,)]
+pub struct Ctx {
+    pub sys_client: i32,
+}
+
+#[schema(Ctx, "notes", crud, executable)]
+#[derive(PartialEq,)]
+pub struct Note {
+    pub body: String,
+}
+
+fn json(s: &str,) -> serde_json::Value {
+    must_be_ok(serde_json::from_str(s,),)
+}
+
+fn note() -> Note {
+    Note {
+        id: 3,
+        sys_client: 7,
+        status: DomainStatus::Inactive,
+        body: "hello".into(),
+        comment: None,
+        tags: json(r#"["a"]"#,),
+        sys_detail: json(r#"{ "source": "import" }"#,),
+        created_by: 1,
+        updated_by: 2,
+        created_at: chrono::Utc::now(),
+        updated_at: chrono::Utc::now(),
+    }
+}
+
+fn insert_row() -> InsertRow {
+    InsertRow {
+        sys_client: 7,
+        status: DomainStatus::Active,
+        body: "hello".into(),
+        comment: None,
+        tags: json("[]",),
+        sys_detail: json("{}",),
+    }
+}
+
+/// The statements fetched so far that start with `prefix`, with their bound values.
+fn fetched(prefix: &str,) -> Vec<(String, Vec<String,>,),> {
+    match sqlx::FETCHED.lock() {
+        Ok(fetched,) => {
+            fetched.iter().filter(|(sql, _,)| sql.starts_with(prefix,),).cloned().collect()
+        }
+        Err(e,) => panic!("{}", e),
+    }
+}
+
+#[mae_test]
+async fn execute_inserts_returning_the_row() {
+    let err = must_be_err(insert_row().execute(&sqlx::PgPool,).await,);
+    must_contains(&err.0, "no rows",);
+
+    let (sql, values,) = must_be_some(fetched("INSERT INTO notes",).pop(),);
+    must_eq(sql, format!("{} {}", insert_row().insert_sql(), Note::returning_clause()),);
+    must_eq(values.len(), 6,);
+    must_eq(values[2].as_str(), "\"hello\"",);
+}
+
+#[mae_test]
+async fn find_by_id_selects_every_column_by_id() {
+    must_be_none(must_be_ok(Note::find_by_id(&sqlx::PgPool, 42,).await,),);
+
+    let (sql, values,) = must_be_some(fetched("SELECT",).pop(),);
+    must_eq(sql, format!("SELECT {} FROM notes WHERE id = $1", Field::All),);
+    must_eq(values, vec!["42".to_string()],);
+}
+
+#[mae_test]
+async fn json_round_trip_yields_an_equal_row() {
+    let value = must_be_ok(note().into_json(),);
+    must_eq(&value["status"], &json(r#""Inactive""#,),);
+    must_eq(&value["comment"], &serde_json::Value::Null,);
+
+    let row = note();
+    must_eq(must_be_ok(Note::from_json(must_be_ok(row.into_json(),),),), row,);
+    must_be_err(Note::from_json(json(r#"{ "id": 3 }"#,),),);
+}